@@ -0,0 +1,166 @@
+//! `#[derive(FromRow)]` for `rust-sqlite3`.
+//!
+//! Use it via the `derive` feature of the `rust-sqlite` crate, which
+//! re-exports it alongside the `FromRow` trait:
+//!
+//! ```rust,ignore
+//! #[derive(FromRow)]
+//! struct Person {
+//!     id: i32,
+//!     #[sqlite3(rename = "full_name")]
+//!     name: String,
+//!     #[sqlite3(default)]
+//!     visits: i64,
+//!     email: Option<String>,
+//! }
+//! ```
+//!
+//! Each field is read from the column of the same name, or the name
+//! given by `rename`. Field types are handled as follows:
+//!
+//!  - `Option<T>`: `None` if the column is missing or `NULL`.
+//!  - `Option<Option<T>>`: `None` if the column is missing,
+//!    `Some(None)` if it is `NULL`.
+//!  - with `#[sqlite3(default)]`: `Default::default()` if the column
+//!    is missing or `NULL`.
+//!  - otherwise, the column must be present; cf `ResultRowAccess::get_opt()`.
+
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+#[macro_use]
+extern crate syn;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as Tokens;
+use syn::{Data, DeriveInput, Field, Fields, GenericArgument, LitStr, PathArguments, Type};
+
+/// Implement `sqlite3::FromRow` for a struct with named fields.
+#[proc_macro_derive(FromRow, attributes(sqlite3))]
+pub fn derive_from_row(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match from_row_impl(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn from_row_impl(input: &DeriveInput) -> syn::Result<Tokens> {
+    let fields = match input.data {
+        Data::Struct(ref data) => {
+            match data.fields {
+                Fields::Named(ref fields) => &fields.named,
+                _ => {
+                    return Err(syn::Error::new_spanned(input,
+                                                       "FromRow needs a struct with named fields"))
+                }
+            }
+        }
+        _ => return Err(syn::Error::new_spanned(input, "FromRow can only be derived for structs")),
+    };
+
+    let mut inits = vec![];
+    for field in fields.iter() {
+        inits.push(field_init(field)?);
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::sqlite3::FromRow for #name #ty_generics #where_clause {
+            fn from_row(row: &mut ::sqlite3::ResultRow) -> ::sqlite3::SqliteResult<Self> {
+                ::std::result::Result::Ok(#name {
+                    #(#inits),*
+                })
+            }
+        }
+    })
+}
+
+/// Options given in `#[sqlite3(...)]` on a field.
+struct FieldAttrs {
+    rename: Option<LitStr>,
+    default: bool,
+}
+
+fn field_attrs(field: &Field) -> syn::Result<FieldAttrs> {
+    let mut attrs = FieldAttrs {
+        rename: None,
+        default: false,
+    };
+    for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("sqlite3")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                attrs.rename = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("default") {
+                attrs.default = true;
+                Ok(())
+            } else {
+                Err(meta.error("expected `rename = \"...\"` or `default`"))
+            }
+        })?;
+    }
+    Ok(attrs)
+}
+
+/// The `T` in `Option<T>`, if `ty` is spelled that way.
+fn option_arg(ty: &Type) -> Option<&Type> {
+    let path = match *ty {
+        Type::Path(ref ty) if ty.qself.is_none() => &ty.path,
+        _ => return None,
+    };
+    let last = match path.segments.last() {
+        Some(last) if last.ident == "Option" => last,
+        _ => return None,
+    };
+    match last.arguments {
+        PathArguments::AngleBracketed(ref args) if args.args.len() == 1 => {
+            match args.args[0] {
+                GenericArgument::Type(ref arg) => Some(arg),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn field_init(field: &Field) -> syn::Result<Tokens> {
+    let attrs = field_attrs(field)?;
+    let ident = field.ident.as_ref().unwrap();
+    let column = match attrs.rename {
+        Some(name) => name,
+        None => LitStr::new(&ident.to_string(), ident.span()),
+    };
+    let ty = &field.ty;
+    let get = quote!(::sqlite3::ResultRowAccess::get_opt(row, ix)?);
+
+    let value = if attrs.default {
+        quote! {
+            match ::sqlite3::RowIndex::idx(&#column, row) {
+                ::std::option::Option::Some(ix) => {
+                    let value: ::std::option::Option<#ty> = #get;
+                    value.unwrap_or_default()
+                }
+                ::std::option::Option::None => ::std::default::Default::default(),
+            }
+        }
+    } else if let Some(inner) = option_arg(ty) {
+        // For Option<Option<T>>, the inner Option takes the NULL.
+        let found = if option_arg(inner).is_some() {
+            quote!(::std::option::Option::Some(#get))
+        } else {
+            get.clone()
+        };
+        quote! {
+            match ::sqlite3::RowIndex::idx(&#column, row) {
+                ::std::option::Option::Some(ix) => #found,
+                ::std::option::Option::None => ::std::option::Option::None,
+            }
+        }
+    } else {
+        quote!(::sqlite3::ResultRowAccess::get_opt(row, #column)?)
+    };
+    Ok(quote!(#ident: #value))
+}
@@ -0,0 +1,244 @@
+//! Incremental I/O on BLOBs.
+//!
+//! Rather than loading a whole blob into memory with
+//! `ResultRow::column_blob()`, open a `Blob` handle with
+//! `DatabaseConnection::blob_open()` and use the `std::io` traits
+//! `Read`, `Write` and `Seek`.
+//!
+//! A blob can't change size through this interface; to store a large
+//! blob, insert a `ZeroBlob` of the right length and then write into it.
+//!
+//! ```rust
+//! extern crate sqlite3;
+//!
+//! use std::io::{Read, Write};
+//! use sqlite3::{DatabaseConnection, StatementUpdate};
+//! use sqlite3::types::ZeroBlob;
+//!
+//! pub fn main() {
+//!     let mut conn = DatabaseConnection::in_memory().unwrap();
+//!     conn.exec("create table files (content blob)").unwrap();
+//!     {
+//!         let mut ins = conn.prepare("insert into files values ($1)").unwrap();
+//!         ins.update(&[&ZeroBlob(5)]).unwrap();
+//!     }
+//!     let rowid = conn.last_insert_rowid();
+//!
+//!     let mut blob = conn.blob_open("main", "files", "content", rowid, true).unwrap();
+//!     blob.write_all(b"hello").unwrap();
+//!
+//!     let mut blob = conn.blob_open("main", "files", "content", rowid, false).unwrap();
+//!     let mut content = String::new();
+//!     blob.read_to_string(&mut content).unwrap();
+//!     assert_eq!(content, "hello");
+//! }
+//! ```
+
+use libc::{c_int, c_void};
+use std::cmp;
+use std::io;
+use std::marker::PhantomData;
+use std::ptr;
+
+use super::{SqliteError, SqliteResult};
+use core::{DatabaseConnection, decode_result, str_charstar};
+use ffi;
+
+/// A handle for incremental I/O on one BLOB.
+///
+/// cf [sqlite3_blob_open][open]
+///
+/// [open]: http://www.sqlite.org/c3ref/blob_open.html
+pub struct Blob<'conn> {
+    blob: *mut ffi::sqlite3_blob,
+    detail_db: Option<*mut ffi::sqlite3>,
+    pos: c_int,
+    conn: PhantomData<&'conn DatabaseConnection>,
+}
+
+impl<'conn> Blob<'conn> {
+    pub(crate) fn open(db: *mut ffi::sqlite3,
+                       detailed: bool,
+                       db_name: &str,
+                       table: &str,
+                       column: &str,
+                       rowid: i64,
+                       read_write: bool)
+                       -> SqliteResult<Blob<'conn>> {
+        let mut blob = ptr::null_mut();
+        let z_db = str_charstar(db_name);
+        let z_table = str_charstar(table);
+        let z_column = str_charstar(column);
+        let flags = if read_write { 1 } else { 0 };
+        let detail_db = if detailed { Some(db) } else { None };
+        let r = unsafe {
+            ffi::sqlite3_blob_open(db,
+                                   z_db.as_ptr(),
+                                   z_table.as_ptr(),
+                                   z_column.as_ptr(),
+                                   rowid,
+                                   flags,
+                                   &mut blob)
+        };
+        match decode_result(r, "sqlite3_blob_open", detail_db) {
+            Ok(()) => {
+                Ok(Blob {
+                    blob: blob,
+                    detail_db: detail_db,
+                    pos: 0,
+                    conn: PhantomData,
+                })
+            }
+            Err(err) => {
+                // "Calling this function with a null pointer (such as
+                // would be returned by a failed call to
+                // sqlite3_blob_open()) is a harmless no-op."
+                unsafe { ffi::sqlite3_blob_close(blob) };
+                Err(err)
+            }
+        }
+    }
+
+    /// Move the handle to the same column of another row, and rewind.
+    ///
+    /// cf `sqlite3_blob_reopen`
+    pub fn reopen(&mut self, rowid: i64) -> SqliteResult<()> {
+        let r = unsafe { ffi::sqlite3_blob_reopen(self.blob, rowid) };
+        self.pos = 0;
+        decode_result(r, "sqlite3_blob_reopen", self.detail_db)
+    }
+
+    /// Size of the blob in bytes.
+    ///
+    /// cf `sqlite3_blob_bytes`
+    pub fn len(&self) -> usize {
+        unsafe { ffi::sqlite3_blob_bytes(self.blob) as usize }
+    }
+
+    /// Test whether the blob is of size 0.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Bytes from the current position to the end, at most `n`.
+    fn available(&self, n: usize) -> c_int {
+        let rest = self.len() as c_int - self.pos;
+        cmp::min(cmp::max(rest, 0) as usize, n) as c_int
+    }
+}
+
+fn io_error(err: SqliteError) -> io::Error {
+    io::Error::other(err)
+}
+
+impl<'conn> io::Read for Blob<'conn> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.available(buf.len());
+        if n == 0 {
+            return Ok(0);
+        }
+        let r = unsafe {
+            ffi::sqlite3_blob_read(self.blob, buf.as_mut_ptr() as *mut c_void, n, self.pos)
+        };
+        try!(decode_result(r, "sqlite3_blob_read", self.detail_db).map_err(io_error));
+        self.pos += n;
+        Ok(n as usize)
+    }
+}
+
+impl<'conn> io::Write for Blob<'conn> {
+    /// Write at the current position.
+    ///
+    /// A blob can't grow, so at the end, this writes 0 bytes.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.available(buf.len());
+        if n == 0 {
+            return Ok(0);
+        }
+        let r = unsafe {
+            ffi::sqlite3_blob_write(self.blob, buf.as_ptr() as *const c_void, n, self.pos)
+        };
+        try!(decode_result(r, "sqlite3_blob_write", self.detail_db).map_err(io_error));
+        self.pos += n;
+        Ok(n as usize)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'conn> io::Seek for Blob<'conn> {
+    /// Seek within the blob; seeking before the start or past the end
+    /// is an `InvalidInput` error.
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            io::SeekFrom::Start(offset) => offset as i64,
+            io::SeekFrom::End(offset) => self.len() as i64 + offset,
+            io::SeekFrom::Current(offset) => self.pos as i64 + offset,
+        };
+        if target < 0 || target > self.len() as i64 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                      "seek outside of blob"));
+        }
+        self.pos = target as c_int;
+        Ok(target as u64)
+    }
+}
+
+impl<'conn> Drop for Blob<'conn> {
+    fn drop(&mut self) {
+        // Any error was reported by an earlier read or write.
+        unsafe { ffi::sqlite3_blob_close(self.blob) };
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+    use core::DatabaseConnection;
+    use types::ZeroBlob;
+    use super::super::StatementUpdate;
+
+    #[test]
+    fn write_seek_read() {
+        let mut conn = DatabaseConnection::in_memory().unwrap();
+        conn.exec("create table t (b blob)").unwrap();
+        {
+            let mut ins = conn.prepare("insert into t values ($1)").unwrap();
+            ins.update(&[&ZeroBlob(8)]).unwrap();
+            ins.update(&[&ZeroBlob(3)]).unwrap();
+        }
+
+        let mut blob = conn.blob_open("main", "t", "b", 1, true).unwrap();
+        assert_eq!(blob.len(), 8);
+        blob.write_all(b"abcd").unwrap();
+        assert_eq!(blob.seek(SeekFrom::End(-2)).unwrap(), 6);
+        let err = blob.write_all(b"xyz").err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::WriteZero);
+
+        blob.seek(SeekFrom::Start(2)).unwrap();
+        let mut buf = vec![];
+        blob.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, b"cd\0\0xy".to_vec());
+        assert!(blob.seek(SeekFrom::Current(1)).is_err());
+
+        blob.reopen(2).unwrap();
+        assert_eq!(blob.len(), 3);
+    }
+
+    #[test]
+    fn read_only() {
+        let mut conn = DatabaseConnection::in_memory().unwrap();
+        conn.exec("create table t (b blob); insert into t values (x'01')").unwrap();
+        let mut blob = conn.blob_open("main", "t", "b", 1, false).unwrap();
+        let err = blob.write(b"z").err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::Other);
+        assert!(conn.blob_open("main", "t", "b", 99, false).is_err());
+    }
+}
+
+// Local Variables:
+// flycheck-rust-crate-root: "lib.rs"
+// End:
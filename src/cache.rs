@@ -0,0 +1,219 @@
+//! A cache of prepared statements, keyed by SQL text.
+//!
+//! `DatabaseConnection::prepare_cached()` saves the cost of
+//! `sqlite3_prepare_v2` for statements that are executed over and
+//! over. The cache holds a bounded number of statements, discarding
+//! the least recently used.
+//!
+//! ```rust
+//! extern crate sqlite3;
+//!
+//! use sqlite3::{DatabaseConnection, StatementUpdate};
+//!
+//! pub fn main() {
+//!     let mut conn = DatabaseConnection::in_memory().unwrap();
+//!     conn.exec("create table t (x integer)").unwrap();
+//!     for x in 0..10 {
+//!         let mut stmt = conn.prepare_cached("insert into t values ($1)").unwrap();
+//!         stmt.update(&[&x]).unwrap();
+//!     }
+//! }
+//! ```
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::mem;
+use std::ops::{Deref, DerefMut};
+
+use super::SqliteResult;
+use core::PreparedStatement;
+
+/// Number of statements a connection caches unless told otherwise.
+pub const DEFAULT_CACHE_CAPACITY: usize = 16;
+
+/// Least-recently-used statements, most recent first.
+pub(crate) struct StatementCache {
+    capacity: usize,
+    entries: RefCell<VecDeque<(String, PreparedStatement)>>,
+}
+
+impl StatementCache {
+    pub(crate) fn new() -> StatementCache {
+        StatementCache {
+            capacity: DEFAULT_CACHE_CAPACITY,
+            entries: RefCell::new(VecDeque::new()),
+        }
+    }
+
+    /// Take the statement for `sql` out of the cache, or prepare one.
+    pub(crate) fn get<F>(&self, sql: &str, prepare: F) -> SqliteResult<CachedStatement<'_>>
+        where F: FnOnce() -> SqliteResult<PreparedStatement>
+    {
+        let cached = {
+            let mut entries = self.entries.borrow_mut();
+            let pos = entries.iter().position(|(key, _)| key == sql);
+            pos.and_then(|ix| entries.remove(ix))
+        };
+        let (sql, stmt) = match cached {
+            Some(entry) => entry,
+            None => (sql.to_string(), try!(prepare())),
+        };
+        Ok(CachedStatement {
+            cache: self,
+            sql: sql,
+            stmt: Some(stmt),
+        })
+    }
+
+    pub(crate) fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.entries.borrow_mut().truncate(capacity);
+    }
+
+    pub(crate) fn flush(&mut self) {
+        self.entries.borrow_mut().clear();
+    }
+
+    /// Return a statement to the cache, unless another statement for
+    /// the same `sql` (checked out at the same time) is already there.
+    fn put_back(&self, sql: String, stmt: PreparedStatement) {
+        let mut entries = self.entries.borrow_mut();
+        if entries.iter().any(|(key, _)| *key == sql) {
+            return;
+        }
+        entries.push_front((sql, stmt));
+        entries.truncate(self.capacity);
+    }
+}
+
+/// A prepared statement on loan from a connection's cache.
+///
+/// It dereferences to `PreparedStatement`. When dropped, the statement
+/// is reset, its bindings are cleared, and it goes back to the cache.
+pub struct CachedStatement<'conn> {
+    cache: &'conn StatementCache,
+    sql: String,
+    stmt: Option<PreparedStatement>,
+}
+
+impl<'conn> CachedStatement<'conn> {
+    /// Drop the statement rather than returning it to the cache.
+    pub fn discard(mut self) {
+        self.stmt = None;
+    }
+}
+
+impl<'conn> Deref for CachedStatement<'conn> {
+    type Target = PreparedStatement;
+
+    fn deref(&self) -> &PreparedStatement {
+        self.stmt.as_ref().unwrap()
+    }
+}
+
+impl<'conn> DerefMut for CachedStatement<'conn> {
+    fn deref_mut(&mut self) -> &mut PreparedStatement {
+        self.stmt.as_mut().unwrap()
+    }
+}
+
+impl<'conn> Drop for CachedStatement<'conn> {
+    fn drop(&mut self) {
+        if let Some(mut stmt) = self.stmt.take() {
+            stmt.reset();
+            stmt.clear_bindings();
+            let sql = mem::take(&mut self.sql);
+            self.cache.put_back(sql, stmt);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use core::DatabaseConnection;
+    use ffi;
+    use std::ptr;
+    use super::super::{QueryEach, ResultRowAccess, StatementUpdate};
+
+    #[test]
+    fn reuses_statements() {
+        let mut conn = DatabaseConnection::in_memory().unwrap();
+        conn.exec("create table t (x integer)").unwrap();
+        let first = {
+            let mut stmt = conn.prepare_cached("insert into t values ($1)").unwrap();
+            stmt.update(&[&1]).unwrap();
+            unsafe { stmt.expose() }
+        };
+        let mut stmt = conn.prepare_cached("insert into t values ($1)").unwrap();
+        assert_eq!(unsafe { stmt.expose() }, first);
+    }
+
+    #[test]
+    fn clears_bindings() {
+        let conn = DatabaseConnection::in_memory().unwrap();
+        {
+            let mut stmt = conn.prepare_cached("select $1").unwrap();
+            stmt.bind_int(1, 42).unwrap();
+        }
+        let mut stmt = conn.prepare_cached("select $1").unwrap();
+        let mut seen = vec![];
+        stmt.query_each(&[], &mut |row| {
+                seen.push(row.get::<u32, Option<i32>>(0));
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(seen, vec![None]);
+    }
+
+    fn live_statements(conn: &mut DatabaseConnection) -> usize {
+        let db = unsafe { conn.expose() };
+        let mut count = 0;
+        let mut stmt = unsafe { ffi::sqlite3_next_stmt(db, ptr::null_mut()) };
+        while !stmt.is_null() {
+            count += 1;
+            stmt = unsafe { ffi::sqlite3_next_stmt(db, stmt) };
+        }
+        count
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut conn = DatabaseConnection::in_memory().unwrap();
+        conn.set_cache_capacity(2);
+        let one = {
+            let mut stmt = conn.prepare_cached("select 1").unwrap();
+            unsafe { stmt.expose() }
+        };
+        for sql in ["select 2", "select 1", "select 3"].iter() {
+            conn.prepare_cached(sql).unwrap();
+        }
+        assert_eq!(live_statements(&mut conn), 2);
+        // "select 2" was evicted; "select 1" was not.
+        let mut stmt = conn.prepare_cached("select 1").unwrap();
+        assert_eq!(unsafe { stmt.expose() }, one);
+    }
+
+    #[test]
+    fn no_duplicate_entries() {
+        let mut conn = DatabaseConnection::in_memory().unwrap();
+        {
+            let _a = conn.prepare_cached("select 1").unwrap();
+            let _b = conn.prepare_cached("select 1").unwrap();
+        }
+        assert_eq!(live_statements(&mut conn), 1);
+    }
+
+    #[test]
+    fn flush() {
+        let mut conn = DatabaseConnection::in_memory().unwrap();
+        conn.prepare_cached("select 1").unwrap();
+        assert_eq!(live_statements(&mut conn), 1);
+        conn.flush_cache();
+        assert_eq!(live_statements(&mut conn), 0);
+    }
+}
+
+// Local Variables:
+// flycheck-rust-crate-root: "lib.rs"
+// End:
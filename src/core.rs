@@ -89,7 +89,8 @@
 //!   - `ResultSet` represents, as a rust lifetime, all of the steps
 //!     of one execution of a statement. (*Ideally, it would be an
 //!     Iterator over `ResultRow`s, but the `Iterator::next()`
-//!     function has no lifetime parameter.*) Use of mutable
+//!     function has no lifetime parameter.* See `rows::RowStream`
+//!     for iterator-style combinators.) Use of mutable
 //!     references ensures that its lifetime is subsumed by the
 //!     statement lifetime.  Its destructor resets the statement.
 //!
@@ -97,9 +98,11 @@
 //!
 
 use enum_primitive::FromPrimitive;
-use libc::{c_int, c_char};
+use libc::{c_int, c_char, c_void};
+use std::any::Any;
 use std::ffi as std_ffi;
 use std::mem;
+use std::panic::{self, AssertUnwindSafe};
 use std::ptr;
 use std::slice;
 use std::str;
@@ -115,6 +118,10 @@ pub use super::{SqliteError, SqliteErrorCode, SqliteResult};
 pub use super::ColumnType;
 pub use super::ColumnType::SQLITE_NULL;
 
+use blob::Blob;
+use super::{QueryRow, ToSql};
+use types::{DurationUnit, TimeFormat, Value, ValueRef};
+use cache::{CachedStatement, StatementCache};
 use ffi; // TODO: move to sqlite3-sys crate
 
 
@@ -171,6 +178,21 @@ pub struct DatabaseConnection {
 
     // whether to copy errmsg() to error detail
     detailed: bool,
+
+    // whether statements check column types in from_sql
+    strict: bool,
+
+    // how statements bind times
+    time_format: TimeFormat,
+
+    // how statements store durations
+    duration_unit: DurationUnit,
+
+    // recently used statements for prepare_cached()
+    cache: StatementCache,
+
+    // number of open `Savepoint` guards, to qualify savepoint names
+    pub(crate) savepoint_depth: u32,
 }
 
 
@@ -213,6 +235,11 @@ impl DatabaseConnection {
             Ok(()) => Ok(DatabaseConnection {
                 db: Rc::new(Database { handle: db}),
                 detailed: true,
+                strict: false,
+                time_format: TimeFormat::default(),
+                duration_unit: DurationUnit::default(),
+                cache: StatementCache::new(),
+                savepoint_depth: 0,
             }),
             Err(err) => {
                 // "Whether or not an error occurs when it is opened,
@@ -231,6 +258,24 @@ impl DatabaseConnection {
         self.detailed = false;
     }
 
+    /// Choose strict typing for statements prepared from now on;
+    /// cf `PreparedStatement::set_strict()`.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Choose how statements prepared from now on bind times;
+    /// cf `PreparedStatement::set_time_format()`.
+    pub fn set_time_format(&mut self, format: TimeFormat) {
+        self.time_format = format;
+    }
+
+    /// Choose how statements prepared from now on store durations;
+    /// cf `PreparedStatement::set_duration_unit()`.
+    pub fn set_duration_unit(&mut self, unit: DurationUnit) {
+        self.duration_unit = unit;
+    }
+
 
     /// Create connection to an in-memory database.
     ///
@@ -254,17 +299,76 @@ impl DatabaseConnection {
         }
     }
 
+    /// Prepare/compile an SQL statement, or reuse one from the
+    /// connection's statement cache.
+    ///
+    /// The statement goes back to the cache when the returned
+    /// `CachedStatement` is dropped.
+    pub fn prepare_cached(&self, sql: &str) -> SqliteResult<CachedStatement<'_>> {
+        let mut stmt = try!(self.cache.get(sql, || self.prepare(sql)));
+        stmt.set_strict(self.strict);
+        stmt.set_time_format(self.time_format);
+        stmt.set_duration_unit(self.duration_unit);
+        Ok(stmt)
+    }
+
+    /// Run a query that should result in exactly one row and compute a
+    /// value from it; cf `QueryRow::query_row()`.
+    ///
+    /// The statement is prepared via `prepare_cached()`.
+    pub fn query_row<F, T>(&self, sql: &str, values: &[&ToSql], f: F) -> SqliteResult<T>
+        where F: FnOnce(&mut ResultRow) -> SqliteResult<T>
+    {
+        let mut stmt = try!(self.prepare_cached(sql));
+        stmt.query_row(values, f)
+    }
+
+    /// Run a query that should result in at most one row and compute a
+    /// value from it; cf `QueryRow::query_opt_row()`.
+    pub fn query_opt_row<F, T>(&self,
+                               sql: &str,
+                               values: &[&ToSql],
+                               f: F)
+                               -> SqliteResult<Option<T>>
+        where F: FnOnce(&mut ResultRow) -> SqliteResult<T>
+    {
+        let mut stmt = try!(self.prepare_cached(sql));
+        stmt.query_opt_row(values, f)
+    }
+
+    /// Set the maximum number of statements kept by `prepare_cached()`,
+    /// discarding any beyond that.
+    pub fn set_cache_capacity(&mut self, capacity: usize) {
+        self.cache.set_capacity(capacity)
+    }
+
+    /// Discard all cached statements, e.g. after a schema change.
+    pub fn flush_cache(&mut self) {
+        self.cache.flush()
+    }
+
     /// Prepare/compile an SQL statement and give offset to remaining text.
     ///
-    /// *TODO: give caller a safe way to use the offset. Perhaps
-    /// return a &'x str?*
+    /// See `prepare_batch()` for a safe way to work through the
+    /// remaining text.
     pub fn prepare_with_offset(&self, sql: &str) -> SqliteResult<(PreparedStatement, usize)> {
+        let z_sql = str_charstar(sql);
+        let (ps, tail) = try!(self.prepare_raw(z_sql.as_ptr(), sql.len() as c_int));
+        let offset = tail as usize - z_sql.as_ptr() as usize;
+        Ok((ps, offset))
+    }
+
+    /// Prepare the first statement in `n_byte` bytes of `z_sql` (up to
+    /// its 0 byte, if `n_byte` is negative); return it along with a
+    /// pointer to the remaining text.
+    fn prepare_raw(&self,
+                   z_sql: *const c_char,
+                   n_byte: c_int)
+                   -> SqliteResult<(PreparedStatement, *const c_char)> {
         let mut stmt = ptr::null_mut();
         let mut tail = ptr::null();
-        let z_sql = str_charstar(sql);
-        let n_byte = sql.len() as c_int;
         let r = unsafe {
-            ffi::sqlite3_prepare_v2(self.db.handle, z_sql.as_ptr(), n_byte, &mut stmt, &mut tail)
+            ffi::sqlite3_prepare_v2(self.db.handle, z_sql, n_byte, &mut stmt, &mut tail)
         };
         match decode_result(r,
                             "sqlite3_prepare_v2",
@@ -273,14 +377,71 @@ impl DatabaseConnection {
                 let ps = PreparedStatement {
                     stmt: stmt,
                     db: self.db.clone(),
-                    detailed: self.detailed };
-                let offset = tail as usize - z_sql.as_ptr() as usize;
-                Ok((ps, offset))
+                    detailed: self.detailed,
+                    strict: self.strict,
+                    time_format: self.time_format,
+                    duration_unit: self.duration_unit,
+                    borrowed: false,
+                };
+                Ok((ps, tail))
             }
             Err(code) => Err(code),
         }
     }
 
+    /// Prepare each of the statements in a script, one at a time.
+    ///
+    /// The resulting iterator yields each statement along with the
+    /// slice of `sql` it was compiled from. Whitespace, comments and
+    /// empty statements (stray `;`) between statements are skipped;
+    /// neither the slice nor the offset below includes them.
+    /// Iteration stops after the first error; `Batch::offset()` then
+    /// tells where in `sql` the offending statement begins.
+    ///
+    /// A script with an internal 0 byte fails with `SQLITE_MISUSE`
+    /// before any statement is prepared, and an unterminated `/*`
+    /// comment with `SQLITE_ERROR`.
+    pub fn prepare_batch<'conn, 'sql>(&'conn self, sql: &'sql str) -> Batch<'conn, 'sql> {
+        let script = match std_ffi::CString::new(sql.as_bytes()) {
+            Ok(c_sql) => Ok(c_sql),
+            Err(err) => {
+                Err(SqliteError {
+                    kind: SqliteErrorCode::SQLITE_MISUSE,
+                    desc: "Sql string contained an internal 0 byte",
+                    detail: Some(format!("at offset {}", err.nul_position())),
+                })
+            }
+        };
+        Batch {
+            conn: self,
+            sql: sql,
+            script: Some(script),
+            offset: 0,
+        }
+    }
+
+    /// Open a BLOB for incremental I/O.
+    ///
+    /// The blob is in `column` of the row with `rowid` in `table` of
+    /// database `db_name` (e.g. `"main"`).
+    ///
+    /// cf `sqlite3_blob_open`
+    pub fn blob_open(&self,
+                     db_name: &str,
+                     table: &str,
+                     column: &str,
+                     rowid: i64,
+                     read_write: bool)
+                     -> SqliteResult<Blob<'_>> {
+        Blob::open(self.db.handle,
+                   self.detailed,
+                   db_name,
+                   table,
+                   column,
+                   rowid,
+                   read_write)
+    }
+
     /// Return a copy of the latest error message.
     ///
     /// Return `""` in case of ill-formed utf-8 or null.
@@ -304,19 +465,88 @@ impl DatabaseConnection {
     ///
     /// cf [sqlite3_exec][exec]
     /// [exec]: http://www.sqlite.org/c3ref/exec.html
-    ///
-    ///  - TODO: callback support?
-    ///  - TODO: errmsg support
     pub fn exec(&mut self, sql: &str) -> SqliteResult<()> {
-        let c_sql = try!(std_ffi::CString::new(sql.as_bytes()));
-        let result = unsafe {
-            ffi::sqlite3_exec(self.db.handle,
-                              c_sql.as_ptr(),
-                              None,
-                              ptr::null_mut(),
-                              ptr::null_mut())
+        exec_raw(self.db.handle, self.detailed, sql, None, ptr::null_mut())
+    }
+
+    /// One-Step Query Execution Interface, with a callback for each
+    /// row of results.
+    ///
+    /// `each_row` gets the (textual) values and the names of the
+    /// columns of each row; a `NULL` value is given as `None`.
+    /// To abort execution, it returns `false`, in which case `exec_with`
+    /// fails with `SQLITE_ABORT`. A value that is not valid UTF-8
+    /// (e.g. a BLOB) aborts execution with `SQLITE_MISMATCH` before
+    /// `each_row` sees that row.
+    ///
+    /// cf [sqlite3_exec][exec]
+    /// [exec]: http://www.sqlite.org/c3ref/exec.html
+    pub fn exec_with<F>(&mut self, sql: &str, each_row: F) -> SqliteResult<()>
+        where F: FnMut(&[Option<&str>], &[&str]) -> bool
+    {
+        struct Context<F> {
+            each_row: F,
+            panic: Option<Box<Any + Send>>,
+            error: Option<SqliteError>,
+        }
+
+        extern "C" fn callback<F>(data: *mut c_void,
+                                  n: c_int,
+                                  values: *mut *mut c_char,
+                                  names: *mut *mut c_char)
+                                  -> c_int
+            where F: FnMut(&[Option<&str>], &[&str]) -> bool
+        {
+            let cx = unsafe { &mut *(data as *mut Context<F>) };
+            let values = unsafe { charstar_array(values, n) };
+            let names = unsafe { charstar_array(names, n) };
+            let mut texts = Vec::with_capacity(values.len());
+            for (col, value) in values.iter().enumerate() {
+                match charstar_bytes(value).map(str::from_utf8) {
+                    None => texts.push(None),
+                    Some(Ok(text)) => texts.push(Some(text)),
+                    Some(Err(err)) => {
+                        cx.error = Some(SqliteError {
+                            kind: SqliteErrorCode::SQLITE_MISMATCH,
+                            desc: "text is not valid UTF-8",
+                            detail: Some(format!("column {}: {}", col, err)),
+                        });
+                        return 1;
+                    }
+                }
+            }
+            let values = texts;
+            let names: Vec<&str> = names.iter().map(|n| charstar_str(n).unwrap_or("")).collect();
+
+            // Don't unwind into sqlite; resume after it returns.
+            let each_row = &mut cx.each_row;
+            match panic::catch_unwind(AssertUnwindSafe(|| each_row(&values, &names))) {
+                Ok(true) => 0,
+                Ok(false) => 1,
+                Err(payload) => {
+                    cx.panic = Some(payload);
+                    1
+                }
+            }
+        }
+
+        let mut cx = Context {
+            each_row: each_row,
+            panic: None,
+            error: None,
         };
-        decode_result(result, "sqlite3_exec", maybe(self.detailed, self.db.handle))
+        let result = exec_raw(self.db.handle,
+                              self.detailed,
+                              sql,
+                              Some(callback::<F>),
+                              &mut cx as *mut Context<F> as *mut c_void);
+        if let Some(payload) = cx.panic {
+            panic::resume_unwind(payload);
+        }
+        match cx.error {
+            Some(err) => Err(err),
+            None => result,
+        }
     }
 
     /// Return the number of database rows that were changed or
@@ -348,6 +578,14 @@ impl DatabaseConnection {
         unsafe { ffi::sqlite3_last_insert_rowid(self.db.handle) }
     }
 
+    /// Test whether the connection is in autocommit mode, i.e.
+    /// not inside an explicit transaction.
+    ///
+    /// cf `sqlite3_get_autocommit`
+    pub fn get_autocommit(&self) -> bool {
+        unsafe { ffi::sqlite3_get_autocommit(self.db.handle) != 0 }
+    }
+
     /// Expose the underlying `sqlite3` struct pointer for use
     /// with the `ffi` module.
     pub unsafe fn expose(&mut self) -> *mut ffi::sqlite3 {
@@ -356,6 +594,173 @@ impl DatabaseConnection {
 }
 
 
+/// A transaction begun on behalf of a statement, e.g. by
+/// `execute_many()`; rolled back on drop unless committed.
+pub(crate) struct StatementTransaction {
+    db: Rc<Database>,
+    detailed: bool,
+    finished: bool,
+}
+
+impl StatementTransaction {
+    /// Commit, rolling back if the `COMMIT` fails.
+    pub(crate) fn commit(mut self) -> SqliteResult<()> {
+        self.finished = true;
+        let result = exec_raw(self.db.handle, self.detailed, "COMMIT", None, ptr::null_mut());
+        if result.is_err() {
+            self.rollback();
+        }
+        result
+    }
+
+    fn rollback(&self) {
+        // already rolled back by sqlite, if in autocommit mode
+        if unsafe { ffi::sqlite3_get_autocommit(self.db.handle) } == 0 {
+            let _ = exec_raw(self.db.handle, false, "ROLLBACK", None, ptr::null_mut());
+        }
+    }
+}
+
+impl Drop for StatementTransaction {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.rollback();
+        }
+    }
+}
+
+
+/// The statements of an SQL script, prepared one at a time.
+///
+/// cf `DatabaseConnection::prepare_batch()`
+pub struct Batch<'conn, 'sql> {
+    conn: &'conn DatabaseConnection,
+    sql: &'sql str,
+    // the whole script as a C string, converted once; `None` when done
+    script: Option<SqliteResult<std_ffi::CString>>,
+    offset: usize,
+}
+
+impl<'conn, 'sql> Batch<'conn, 'sql> {
+    /// Byte offset into the script of the text not yet prepared.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Stop iterating after `err`.
+    fn fail(&mut self,
+            err: SqliteError)
+            -> Option<SqliteResult<(PreparedStatement, &'sql str)>> {
+        self.script = None;
+        Some(Err(err))
+    }
+}
+
+impl<'conn, 'sql> Iterator for Batch<'conn, 'sql> {
+    type Item = SqliteResult<(PreparedStatement, &'sql str)>;
+
+    fn next(&mut self) -> Option<SqliteResult<(PreparedStatement, &'sql str)>> {
+        let z_sql = match self.script.take() {
+            None => return None,
+            Some(Err(err)) => return Some(Err(err)),
+            Some(Ok(c_sql)) => c_sql,
+        };
+        match blank_len(&self.sql[self.offset..]) {
+            Ok(len) => self.offset += len,
+            Err(at) => {
+                self.offset += at;
+                return self.fail(SqliteError {
+                    kind: SqliteErrorCode::SQLITE_ERROR,
+                    desc: "unterminated comment",
+                    detail: Some(format!("at offset {}", self.offset)),
+                });
+            }
+        }
+        let rest = &self.sql[self.offset..];
+        if rest.is_empty() {
+            return None;
+        }
+        let start = unsafe { z_sql.as_ptr().offset(self.offset as isize) };
+        let (stmt, tail) = match self.conn.prepare_raw(start, -1) {
+            Ok(ok) => ok,
+            // leave offset at the start of the bad statement
+            Err(err) => return self.fail(err),
+        };
+        let len = tail as usize - start as usize;
+        if stmt.stmt.is_null() || len == 0 {
+            // only if sqlite sees blanks that blank_len() doesn't
+            return None;
+        }
+        self.offset += len;
+        self.script = Some(Ok(z_sql));
+        Some(Ok((stmt, &rest[..len])))
+    }
+}
+
+/// Length of the whitespace, comments and `;` at the start of `sql`,
+/// or the offset of an unterminated `/*` comment.
+fn blank_len(sql: &str) -> Result<usize, usize> {
+    let mut rest = sql;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ';');
+        if rest.starts_with("--") {
+            rest = match rest.find('\n') {
+                Some(end) => &rest[end + 1..],
+                None => "",
+            };
+        } else if rest.starts_with("/*") {
+            rest = match rest[2..].find("*/") {
+                Some(end) => &rest[end + 4..],
+                None => return Err(sql.len() - rest.len()),
+            };
+        } else {
+            return Ok(sql.len() - rest.len());
+        }
+    }
+}
+
+
+/// Run `sql` on the `db` handle via `sqlite3_exec`.
+fn exec_raw(db: *mut ffi::sqlite3,
+            detailed: bool,
+            sql: &str,
+            callback: Option<extern "C" fn(*mut c_void,
+                                           c_int,
+                                           *mut *mut c_char,
+                                           *mut *mut c_char)
+                                           -> c_int>,
+            data: *mut c_void)
+            -> SqliteResult<()> {
+    let c_sql = try!(std_ffi::CString::new(sql.as_bytes()));
+    let mut errmsg = ptr::null_mut();
+    let result = unsafe { ffi::sqlite3_exec(db, c_sql.as_ptr(), callback, data, &mut errmsg) };
+    let detail = if errmsg.is_null() {
+        maybe(detailed, db).map(DatabaseConnection::_errmsg)
+    } else {
+        let msg = charstar_str(&(errmsg as *const c_char)).unwrap_or("").to_string();
+        unsafe { ffi::sqlite3_free(errmsg as *mut c_void) };
+        maybe(detailed, msg)
+    };
+    if result == SQLITE_OK as c_int {
+        Ok(())
+    } else {
+        Err(error_result(result, "sqlite3_exec", detail))
+    }
+}
+
+/// Length of text or a blob to bind, as sqlite takes it; `SQLITE_TOOBIG`
+/// rather than wrapping.
+fn bind_len(len: usize) -> SqliteResult<c_int> {
+    if len > c_int::MAX as usize {
+        return Err(SqliteError {
+            kind: SqliteErrorCode::SQLITE_TOOBIG,
+            desc: "value too long to bind",
+            detail: Some(format!("{} bytes", len)),
+        });
+    }
+    Ok(len as c_int)
+}
+
 /// Convert from sqlite3 API utf8 to rust str.
 fn charstar_str(utf_bytes: &*const c_char) -> Option<&str> {
     if utf_bytes.is_null() {
@@ -366,6 +771,23 @@ fn charstar_str(utf_bytes: &*const c_char) -> Option<&str> {
     Some(unsafe { str::from_utf8_unchecked(c_str.to_bytes()) })
 }
 
+/// View a C string from the sqlite3 API as bytes, without assuming utf8.
+fn charstar_bytes(bytes: &*const c_char) -> Option<&[u8]> {
+    if bytes.is_null() {
+        return None;
+    }
+    Some(unsafe { CStr::from_ptr(*bytes) }.to_bytes())
+}
+
+/// View an array of `n` C strings from the sqlite3 API as a slice.
+unsafe fn charstar_array<'a>(array: *mut *mut c_char, n: c_int) -> &'a [*const c_char] {
+    if array.is_null() {
+        &[]
+    } else {
+        slice::from_raw_parts(array as *const *const c_char, n as usize)
+    }
+}
+
 /// Convenience function to get a `CString` from a str
 #[inline(always)]
 pub fn str_charstar(s: &str) -> std_ffi::CString {
@@ -377,6 +799,12 @@ pub struct PreparedStatement {
     db: Rc<Database>,
     stmt: *mut ffi::sqlite3_stmt,
     detailed: bool,
+    strict: bool,
+    time_format: TimeFormat,
+    duration_unit: DurationUnit,
+
+    // whether any parameters are bound to borrowed (SQLITE_STATIC) data
+    borrowed: bool,
 }
 
 impl Drop for PreparedStatement {
@@ -398,6 +826,26 @@ impl Drop for PreparedStatement {
 }
 
 
+/// Runtime counters of a prepared statement.
+///
+/// cf [status parameters for prepared statements][stmtstatus]
+///
+/// [stmtstatus]: http://www.sqlite.org/c3ref/c_stmtstatus_counter.html
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[allow(non_camel_case_types)]
+pub enum StmtStatus {
+    /// Steps taken in full table scans; a large number suggests a
+    /// missing index.
+    SQLITE_STMTSTATUS_FULLSCAN_STEP = 1,
+    /// Sort operations.
+    SQLITE_STMTSTATUS_SORT = 2,
+    /// Rows inserted into transient indices created automatically.
+    SQLITE_STMTSTATUS_AUTOINDEX = 3,
+    /// Virtual machine operations.
+    SQLITE_STMTSTATUS_VM_STEP = 4,
+}
+
+
 /// Type for picking out a bind parameter.
 /// 1-indexed
 pub type ParamIx = u16;
@@ -411,7 +859,15 @@ impl PreparedStatement {
     /// implementing the `Iterator` trait. See the `Query` trait
     /// for and `Iterator` over query results.
     pub fn execute(&mut self) -> ResultSet {
-        ResultSet { statement: self }
+        if self.borrowed {
+            // A ResultSet with borrowed bindings was leaked
+            // (e.g. by mem::forget); its buffers may be gone.
+            self.clear_bindings();
+        }
+        ResultSet {
+            statement: self,
+            at_row: false,
+        }
     }
 }
 
@@ -425,6 +881,59 @@ impl PreparedStatement {
         self.detailed = false;
     }
 
+    /// Choose strict typing for results of this statement.
+    ///
+    /// In strict mode, `FromSql` implementations check `column_type`
+    /// rather than relying on sqlite's silent conversions, failing
+    /// with `SQLITE_MISMATCH` on e.g. `NULL` for a `String` or text
+    /// for an `i32`.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Whether strict typing is in effect; cf `set_strict()`.
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Choose how `time::Timespec` and `time::Tm` parameters are
+    /// bound. Reading times detects the format.
+    pub fn set_time_format(&mut self, format: TimeFormat) {
+        self.time_format = format;
+    }
+
+    /// How times are bound; cf `set_time_format()`.
+    pub fn time_format(&self) -> TimeFormat {
+        self.time_format
+    }
+
+    /// Choose the unit in which `time::Duration` and
+    /// `std::time::Duration` parameters and results are stored.
+    pub fn set_duration_unit(&mut self, unit: DurationUnit) {
+        self.duration_unit = unit;
+    }
+
+    /// Unit of durations; cf `set_duration_unit()`.
+    pub fn duration_unit(&self) -> DurationUnit {
+        self.duration_unit
+    }
+
+
+    /// Begin a deferred transaction on the connection this statement
+    /// belongs to, unless one is already open.
+    ///
+    /// Return `None` if a transaction is already open.
+    pub(crate) fn begin_unless_open(&self) -> SqliteResult<Option<StatementTransaction>> {
+        if unsafe { ffi::sqlite3_get_autocommit(self.db.handle) } == 0 {
+            return Ok(None);
+        }
+        try!(exec_raw(self.db.handle, self.detailed, "BEGIN DEFERRED", None, ptr::null_mut()));
+        Ok(Some(StatementTransaction {
+            db: self.db.clone(),
+            detailed: self.detailed,
+            finished: false,
+        }))
+    }
 
     fn detail_db(&mut self) -> Option<*mut ffi::sqlite3> {
         if self.detailed {
@@ -469,35 +978,70 @@ impl PreparedStatement {
 
     /// Bind a (copy of a) str to a statement parameter.
     ///
-    /// *TODO: support binding without copying strings, blobs*
+    /// The text is passed by length, so it may contain 0 bytes.
+    /// To bind without copying, see `ResultSet::bind_text_ref()`.
     pub fn bind_text(&mut self, i: ParamIx, value: &str) -> SqliteResult<()> {
-        let ix = i as c_int;
         // SQLITE_TRANSIENT => SQLite makes a copy
         let transient = unsafe { mem::transmute(-1 as isize) };
-        let c_value = str_charstar(value);
-        let len = value.len() as c_int;
-        let r = unsafe { ffi::sqlite3_bind_text(self.stmt, ix, c_value.as_ptr(), len, transient) };
+        self.bind_text_with(i, value, transient)
+    }
+
+    fn bind_text_with(&mut self,
+                      i: ParamIx,
+                      value: &str,
+                      destructor: Option<extern "C" fn(*mut c_void)>)
+                      -> SqliteResult<()> {
+        let ix = i as c_int;
+        let len = try!(bind_len(value.len()));
+        let val = value.as_ptr() as *const c_char;
+        let r = unsafe { ffi::sqlite3_bind_text(self.stmt, ix, val, len, destructor) };
         decode_result(r, "sqlite3_bind_text", self.detail_db())
     }
 
     /// Bind a (copy of a) byte sequence to a statement parameter.
     ///
-    /// *TODO: support binding without copying strings, blobs*
+    /// To bind without copying, see `ResultSet::bind_blob_ref()`.
     pub fn bind_blob(&mut self, i: ParamIx, value: &[u8]) -> SqliteResult<()> {
-        let ix = i as c_int;
         // SQLITE_TRANSIENT => SQLite makes a copy
         let transient = unsafe { mem::transmute(-1 as isize) };
-        let len = value.len() as c_int;
-        // from &[u8] to &[i8]
-        let val = unsafe { mem::transmute(value.as_ptr()) };
-        let r = unsafe { ffi::sqlite3_bind_blob(self.stmt, ix, val, len, transient) };
+        self.bind_blob_with(i, value, transient)
+    }
+
+    fn bind_blob_with(&mut self,
+                      i: ParamIx,
+                      value: &[u8],
+                      destructor: Option<extern "C" fn(*mut c_void)>)
+                      -> SqliteResult<()> {
+        let ix = i as c_int;
+        let len = try!(bind_len(value.len()));
+        let val = value.as_ptr() as *const c_void;
+        let r = unsafe { ffi::sqlite3_bind_blob(self.stmt, ix, val, len, destructor) };
         decode_result(r, "sqlite3_bind_blob", self.detail_db())
     }
 
+    /// Bind a blob of `len` zero bytes to a statement parameter.
+    ///
+    /// Use this to make room for a blob to be written with `Blob`.
+    pub fn bind_zeroblob(&mut self, i: ParamIx, len: i32) -> SqliteResult<()> {
+        let ix = i as c_int;
+        let r = unsafe { ffi::sqlite3_bind_zeroblob(self.stmt, ix, len) };
+        decode_result(r, "sqlite3_bind_zeroblob", self.detail_db())
+    }
+
+    /// Reset the statement so that it can be executed again.
+    ///
+    /// Bindings are not affected. Note that dropping a `ResultSet`
+    /// resets its statement.
+    pub fn reset(&mut self) {
+        // Errors were already reported by the last step.
+        unsafe { ffi::sqlite3_reset(self.stmt) };
+    }
+
     /// Clear all parameter bindings.
     pub fn clear_bindings(&mut self) {
         // We ignore the return value, since no return codes are documented.
         unsafe { ffi::sqlite3_clear_bindings(self.stmt) };
+        self.borrowed = false;
     }
 
     /// Return the number of SQL parameters.
@@ -507,6 +1051,106 @@ impl PreparedStatement {
         count as ParamIx
     }
 
+    /// Look up the index of a named parameter.
+    ///
+    /// The name may be given with its prefix (`:name`, `@name` or
+    /// `$name`) or without, in which case each prefix is tried in turn.
+    /// Return `None` if there is no such parameter.
+    ///
+    /// cf `sqlite3_bind_parameter_index`
+    pub fn parameter_index(&self, name: &str) -> Option<ParamIx> {
+        let lookup = |name: &str| {
+            let c_name = str_charstar(name);
+            let ix = unsafe { ffi::sqlite3_bind_parameter_index(self.stmt, c_name.as_ptr()) };
+            maybe(ix > 0, ix as ParamIx)
+        };
+        if name.starts_with([':', '@', '$', '?']) {
+            lookup(name)
+        } else {
+            [":", "@", "$"]
+                .iter()
+                .find_map(|prefix| lookup(&format!("{}{}", prefix, name)))
+        }
+    }
+
+    /// Look up the name of a parameter, including its prefix.
+    ///
+    /// Return `None` for nameless (`?`) parameters and for `i` out of range.
+    ///
+    /// cf `sqlite3_bind_parameter_name`
+    pub fn parameter_name(&self, i: ParamIx) -> Option<String> {
+        let name = unsafe { ffi::sqlite3_bind_parameter_name(self.stmt, i as c_int) };
+        charstar_str(&name).map(|name| name.to_string())
+    }
+
+    /// Return the number of columns in the result set.
+    ///
+    /// This is 0 for a statement that returns no data, e.g. `UPDATE`.
+    ///
+    /// cf `sqlite3_column_count`
+    pub fn column_count(&self) -> ColIx {
+        let result = unsafe { ffi::sqlite3_column_count(self.stmt) };
+        result as ColIx
+    }
+
+    /// Describe the result columns: their names, declared types, and
+    /// where they come from.
+    ///
+    /// Unlike `ResultRow::with_column_name()`, this is available
+    /// before the statement is executed.
+    pub fn columns(&self) -> Vec<ColumnInfo> {
+        let stmt = self.stmt;
+        let text = |ptr: *const c_char| charstar_str(&ptr).map(|s| s.to_string());
+        (0..self.column_count() as c_int)
+            .map(|i| unsafe {
+                ColumnInfo {
+                    name: text(ffi::sqlite3_column_name(stmt, i)).unwrap_or_else(String::new),
+                    decltype: text(ffi::sqlite3_column_decltype(stmt, i)),
+                    database: text(ffi::sqlite3_column_database_name(stmt, i)),
+                    table: text(ffi::sqlite3_column_table_name(stmt, i)),
+                    origin: text(ffi::sqlite3_column_origin_name(stmt, i)),
+                }
+            })
+            .collect()
+    }
+
+    /// Return the SQL text the statement was prepared from.
+    ///
+    /// cf `sqlite3_sql`
+    pub fn sql(&self) -> &str {
+        let sql = unsafe { ffi::sqlite3_sql(self.stmt) };
+        if sql.is_null() {
+            return "";
+        }
+        // it's a copy of the &str we prepared
+        unsafe { str::from_utf8_unchecked(CStr::from_ptr(sql).to_bytes()) }
+    }
+
+    /// Test whether the statement makes no direct changes to the database.
+    ///
+    /// cf `sqlite3_stmt_readonly`
+    pub fn is_readonly(&self) -> bool {
+        unsafe { ffi::sqlite3_stmt_readonly(self.stmt) != 0 }
+    }
+
+    /// Test whether the statement has been stepped but not yet run to
+    /// completion or reset.
+    ///
+    /// Since dropping a `ResultSet` resets the statement, this is only
+    /// `true` for a statement stepped via `ffi`; cf `ResultSet::is_busy()`.
+    ///
+    /// cf `sqlite3_stmt_busy`
+    pub fn is_busy(&self) -> bool {
+        unsafe { ffi::sqlite3_stmt_busy(self.stmt) != 0 }
+    }
+
+    /// Get the value of a runtime counter, optionally resetting it to 0.
+    ///
+    /// cf `sqlite3_stmt_status`
+    pub fn status(&self, op: StmtStatus, reset: bool) -> i32 {
+        unsafe { ffi::sqlite3_stmt_status(self.stmt, op as c_int, reset as c_int) }
+    }
+
     /// Expose the underlying `sqlite3_stmt` struct pointer for use
     /// with the `ffi` module.
     pub unsafe fn expose(&mut self) -> *mut ffi::sqlite3_stmt {
@@ -526,9 +1170,36 @@ impl PreparedStatement {
 }
 
 
+/// Description of a result column.
+///
+/// The `database`, `table` and `origin` are given only for columns
+/// that come directly from a table column, rather than from an
+/// expression or subquery.
+///
+/// cf [sqlite3_column_database_name][meta]
+///
+/// [meta]: http://www.sqlite.org/c3ref/column_database_name.html
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ColumnInfo {
+    /// Name of the column in the result, e.g. as given by `AS`
+    pub name: String,
+    /// Declared type of the table column, e.g. `VARCHAR(40)`
+    pub decltype: Option<String>,
+    /// Database name, e.g. `main`
+    pub database: Option<String>,
+    /// Table name
+    pub table: Option<String>,
+    /// Name of the column in its table
+    pub origin: Option<String>,
+}
+
+
 /// Results of executing a `prepare()`d statement.
 pub struct ResultSet<'res> {
     statement: &'res mut PreparedStatement,
+
+    // whether the last step() produced a row
+    at_row: bool,
 }
 
 enum_from_primitive! {
@@ -550,6 +1221,36 @@ impl<'res> Drop for ResultSet<'res> {
         // statement S indicated an error, then sqlite3_reset(S)
         // returns an appropriate error code."
         unsafe { ffi::sqlite3_reset(self.statement.stmt) };
+
+        // Don't leave the statement pointing at borrowed data.
+        if self.statement.borrowed {
+            self.statement.clear_bindings();
+        }
+    }
+}
+
+
+/// Parameters may be bound to borrowed data, without copying, for the
+/// duration of an execution. The borrow checker ensures the data
+/// outlives the `ResultSet`, whose destructor clears the bindings.
+///
+/// Bind before the first `step()`; sqlite reports `SQLITE_MISUSE`
+/// otherwise.
+impl<'res> ResultSet<'res> {
+    /// Bind a str to a statement parameter without copying it.
+    ///
+    /// cf `SQLITE_STATIC` in `sqlite3_bind_text`
+    pub fn bind_text_ref(&mut self, i: ParamIx, value: &'res str) -> SqliteResult<()> {
+        self.statement.borrowed = true;
+        self.statement.bind_text_with(i, value, None)
+    }
+
+    /// Bind a byte sequence to a statement parameter without copying it.
+    ///
+    /// cf `SQLITE_STATIC` in `sqlite3_bind_blob`
+    pub fn bind_blob_ref(&mut self, i: ParamIx, value: &'res [u8]) -> SqliteResult<()> {
+        self.statement.borrowed = true;
+        self.statement.bind_blob_with(i, value, None)
     }
 }
 
@@ -558,12 +1259,31 @@ impl<'res: 'row, 'row> ResultSet<'res> {
     /// Execute the next step of a prepared statement.
     pub fn step(&'row mut self) -> SqliteResult<Option<ResultRow<'res, 'row>>> {
         let result = unsafe { ffi::sqlite3_step(self.statement.stmt) };
-        match Step::from_i32(result) {
+        let step = Step::from_i32(result);
+        self.at_row = step == Some(SQLITE_ROW);
+        match step {
             Some(SQLITE_ROW) => Ok(Some(ResultRow { rows: self })),
             Some(SQLITE_DONE) => Ok(None),
             None => Err(error_result(result, "step", self.statement.get_detail())),
         }
     }
+
+    /// Get the row produced by the latest `step()` again, if any.
+    pub fn current_row(&'row mut self) -> Option<ResultRow<'res, 'row>> {
+        if self.at_row {
+            Some(ResultRow { rows: self })
+        } else {
+            None
+        }
+    }
+
+    /// Test whether the statement has been stepped but not yet run to
+    /// completion.
+    ///
+    /// cf `sqlite3_stmt_busy`
+    pub fn is_busy(&self) -> bool {
+        self.statement.is_busy()
+    }
 }
 
 
@@ -601,7 +1321,7 @@ impl<'res, 'row> ResultRow<'res, 'row> {
     /// Return `default` if there is no column `i`
     ///
     /// cf `sqlite_column_name`
-    pub fn with_column_name<T, F: Fn(&str) -> T>(&mut self, i: ColIx, default: T, f: F) -> T {
+    pub fn with_column_name<T, F: Fn(&str) -> T>(&self, i: ColIx, default: T, f: F) -> T {
         let stmt = self.rows.statement.stmt;
         let n = i as c_int;
         let result = unsafe { ffi::sqlite3_column_name(stmt, n) };
@@ -611,6 +1331,22 @@ impl<'res, 'row> ResultRow<'res, 'row> {
         }
     }
 
+    /// Whether the statement uses strict typing; cf
+    /// `PreparedStatement::set_strict()`.
+    pub fn is_strict(&self) -> bool {
+        self.rows.statement.strict
+    }
+
+    /// How times are bound; cf `PreparedStatement::set_time_format()`.
+    pub fn time_format(&self) -> TimeFormat {
+        self.rows.statement.time_format
+    }
+
+    /// Unit of durations; cf `PreparedStatement::set_duration_unit()`.
+    pub fn duration_unit(&self) -> DurationUnit {
+        self.rows.statement.duration_unit
+    }
+
     /// Look up the type of a column.
     ///
     /// Return `SQLITE_NULL` if there is no such `col`.
@@ -669,6 +1405,31 @@ impl<'res, 'row> ResultRow<'res, 'row> {
         let len = unsafe { ffi::sqlite3_column_bytes(stmt, i_col) } as usize;
         Some(unsafe { slice::from_raw_parts(bs, len) })
     }
+
+    /// Get the value of a column, borrowing text and blobs, as
+    /// indicated by `column_type`.
+    ///
+    /// Text that is not valid UTF-8 is given as `ValueRef::Blob`.
+    pub fn column_value(&self, col: ColIx) -> ValueRef<'_> {
+        match self.column_type(col) {
+            ColumnType::SQLITE_NULL => ValueRef::Null,
+            ColumnType::SQLITE_INTEGER => ValueRef::Integer(self.column_int64(col)),
+            ColumnType::SQLITE_FLOAT => ValueRef::Real(self.column_double(col)),
+            ColumnType::SQLITE_TEXT => {
+                let bytes = self.column_slice(col).unwrap_or(&[]);
+                match str::from_utf8(bytes) {
+                    Ok(text) => ValueRef::Text(text),
+                    Err(_) => ValueRef::Blob(bytes),
+                }
+            }
+            ColumnType::SQLITE_BLOB => ValueRef::Blob(self.column_slice(col).unwrap_or(&[])),
+        }
+    }
+
+    /// Get the values of all columns; cf `column_value()`.
+    pub fn values(&self) -> Vec<Value> {
+        (0..self.column_count()).map(|col| Value::from(self.column_value(col))).collect()
+    }
 }
 
 
@@ -727,7 +1488,8 @@ mod test_opening {
 
 #[cfg(test)]
 mod tests {
-    use super::{DatabaseConnection, SqliteResult, ResultSet};
+    use super::{ColumnInfo, DatabaseConnection, SqliteErrorCode, SqliteResult, ResultSet};
+    use super::StmtStatus::{SQLITE_STMTSTATUS_FULLSCAN_STEP, SQLITE_STMTSTATUS_VM_STEP};
     use std::str;
 
     #[test]
@@ -790,6 +1552,217 @@ mod tests {
             .unwrap();
     }
 
+    #[test]
+    fn batch_of_statements() {
+        let script = "
+            -- schema
+            create table t (x integer);
+            /* data */
+            insert into t values ($1);
+            select count(*) from t; ";
+        let db = DatabaseConnection::in_memory().unwrap();
+        let mut texts = vec![];
+        for (ix, item) in db.prepare_batch(script).enumerate() {
+            let (mut stmt, text) = item.unwrap();
+            if ix == 1 {
+                stmt.bind_int(1, 7).unwrap();
+            }
+            let mut rows = stmt.execute();
+            if let Some(row) = rows.step().unwrap() {
+                assert_eq!(row.column_int(0), 1);
+            }
+            texts.push(text);
+        }
+        assert_eq!(texts,
+                   vec!["create table t (x integer);",
+                        "insert into t values ($1);",
+                        "select count(*) from t;"]);
+    }
+
+    #[test]
+    fn batch_error_offset() {
+        let script = "select 1; -- next\n /* bad */ select bogus; select 2;";
+        let db = DatabaseConnection::in_memory().unwrap();
+        let mut batch = db.prepare_batch(script);
+        assert!(batch.next().unwrap().is_ok());
+        let err = batch.next().unwrap().err().unwrap();
+        assert_eq!(err.detail(), Some("no such column: bogus".to_string()));
+        assert_eq!(&script[batch.offset()..], "select bogus; select 2;");
+        assert!(batch.next().is_none());
+    }
+
+    #[test]
+    fn batch_stray_semicolons() {
+        let db = DatabaseConnection::in_memory().unwrap();
+        let texts = |script| {
+            db.prepare_batch(script)
+                .map(|item| item.unwrap().1)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(texts("select 1;;; select 2"), vec!["select 1;", "select 2"]);
+        assert_eq!(texts("select 1; ; /* c */ select 2;;"),
+                   vec!["select 1;", "select 2;"]);
+    }
+
+    #[test]
+    fn batch_unterminated_comment() {
+        let script = "select 1; /* oops select 2;";
+        let db = DatabaseConnection::in_memory().unwrap();
+        let mut batch = db.prepare_batch(script);
+        assert!(batch.next().unwrap().is_ok());
+        let err = batch.next().unwrap().err().unwrap();
+        assert_eq!(err.kind, SqliteErrorCode::SQLITE_ERROR);
+        assert_eq!(err.detail(), Some("at offset 10".to_string()));
+        assert_eq!(&script[batch.offset()..], "/* oops select 2;");
+        assert!(batch.next().is_none());
+    }
+
+    #[test]
+    fn batch_with_nul() {
+        let db = DatabaseConnection::in_memory().unwrap();
+        let mut batch = db.prepare_batch("select 1; select 'a\0b'; select 3;");
+        let err = batch.next().unwrap().err().unwrap();
+        assert_eq!(err.kind, SqliteErrorCode::SQLITE_MISUSE);
+        assert_eq!(err.detail(), Some("at offset 19".to_string()));
+        assert!(batch.next().is_none());
+    }
+
+    #[test]
+    fn exec_with_rows() {
+        let mut db = DatabaseConnection::in_memory().unwrap();
+        let mut seen = vec![];
+        db.exec_with("select 1 as a, null as b union all select 2, 'x'",
+                       |values, names| {
+                           assert_eq!(names, &["a", "b"]);
+                           seen.push((values[0].map(|v| v.to_string()),
+                                      values[1].map(|v| v.to_string())));
+                           true
+                       })
+            .unwrap();
+        assert_eq!(seen,
+                   vec![(Some("1".to_string()), None),
+                        (Some("2".to_string()), Some("x".to_string()))]);
+    }
+
+    #[test]
+    fn exec_with_abort() {
+        let mut db = DatabaseConnection::in_memory().unwrap();
+        let mut count = 0;
+        let err = db.exec_with("select 1 union all select 2", |_, _| {
+                count += 1;
+                false
+            })
+            .err()
+            .unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(err.kind, SqliteErrorCode::SQLITE_ABORT);
+        assert_eq!(err.detail(), Some("query aborted".to_string()));
+    }
+
+    #[test]
+    fn exec_with_blob() {
+        let mut db = DatabaseConnection::in_memory().unwrap();
+        let mut count = 0;
+        let err = db.exec_with("select 'ok' union all select x'ff80'", |values, _| {
+                assert_eq!(values, &[Some("ok")]);
+                count += 1;
+                true
+            })
+            .err()
+            .unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(err.kind, SqliteErrorCode::SQLITE_MISMATCH);
+        assert_eq!(err.desc, "text is not valid UTF-8");
+    }
+
+    #[test]
+    fn bind_len_limit() {
+        use super::bind_len;
+        use libc::c_int;
+
+        assert_eq!(bind_len(3), Ok(3));
+        assert_eq!(bind_len(c_int::MAX as usize), Ok(c_int::MAX));
+        let err = bind_len(c_int::MAX as usize + 1).err().unwrap();
+        assert_eq!(err.kind, SqliteErrorCode::SQLITE_TOOBIG);
+        assert_eq!(err.detail(), Some("2147483648 bytes".to_string()));
+    }
+
+    #[test]
+    fn text_with_nul() {
+        let db = DatabaseConnection::in_memory().unwrap();
+        let mut stmt = db.prepare("select $1, length(cast($1 as blob))").unwrap();
+        stmt.bind_text(1, "a\0b").unwrap();
+        let mut rows = stmt.execute();
+        let row = rows.step().unwrap().unwrap();
+        assert_eq!(row.column_str(0), Some("a\0b"));
+        assert_eq!(row.column_int(1), 3);
+    }
+
+    #[test]
+    fn bind_borrowed() {
+        let db = DatabaseConnection::in_memory().unwrap();
+        let mut stmt = db.prepare("select $1, length($2)").unwrap();
+        let text = "borrowed".to_string();
+        let blob = vec![0u8; 1 << 20];
+        {
+            let mut rows = stmt.execute();
+            rows.bind_text_ref(1, &text).unwrap();
+            rows.bind_blob_ref(2, &blob).unwrap();
+            let row = rows.step().unwrap().unwrap();
+            assert_eq!(row.column_str(0), Some("borrowed"));
+            assert_eq!(row.column_int(1), 1 << 20);
+        }
+        // bindings were cleared along with the ResultSet
+        let mut rows = stmt.execute();
+        let row = rows.step().unwrap().unwrap();
+        assert_eq!(row.column_str(0), None);
+    }
+
+    #[test]
+    fn column_info() {
+        let mut db = DatabaseConnection::in_memory().unwrap();
+        db.exec("create table items (id integer, description varchar(40))").unwrap();
+        let stmt = db.prepare("select description as d, id + 1 from items").unwrap();
+        assert_eq!(stmt.column_count(), 2);
+        assert_eq!(stmt.columns(),
+                   vec![ColumnInfo {
+                            name: "d".to_string(),
+                            decltype: Some("varchar(40)".to_string()),
+                            database: Some("main".to_string()),
+                            table: Some("items".to_string()),
+                            origin: Some("description".to_string()),
+                        },
+                        ColumnInfo {
+                            name: "id + 1".to_string(),
+                            decltype: None,
+                            database: None,
+                            table: None,
+                            origin: None,
+                        }]);
+    }
+
+    #[test]
+    fn introspection() {
+        let mut db = DatabaseConnection::in_memory().unwrap();
+        db.exec("create table t (x integer); insert into t values (1), (2)").unwrap();
+        let mut stmt = db.prepare("select x from t where x > 1").unwrap();
+        assert_eq!(stmt.sql(), "select x from t where x > 1");
+        assert!(stmt.is_readonly());
+        assert!(!db.prepare("delete from t").unwrap().is_readonly());
+        {
+            let mut rows = stmt.execute();
+            assert!(!rows.is_busy());
+            rows.step().unwrap();
+            assert!(rows.is_busy());
+            assert!(rows.step().unwrap().is_none());
+            assert!(!rows.is_busy());
+        }
+        assert!(!stmt.is_busy());
+        assert_eq!(stmt.status(SQLITE_STMTSTATUS_FULLSCAN_STEP, true), 1);
+        assert_eq!(stmt.status(SQLITE_STMTSTATUS_FULLSCAN_STEP, false), 0);
+        assert!(stmt.status(SQLITE_STMTSTATUS_VM_STEP, false) > 0);
+    }
+
     #[test]
     fn detailed_errors() {
         let go = || -> SqliteResult<()> {
@@ -0,0 +1,223 @@
+//! JSON documents in TEXT columns, using sqlite's [JSON1][] functions.
+//!
+//! With the `json` feature, `Json<T>` stores any `Serialize` value as
+//! JSON text and reads it back into any `Deserialize` value, so
+//! documents needn't be parsed by hand after `column_text()`.
+//!
+//! `JsonPath` checks a path expression such as `$.tags[0]`, and
+//! `extract_sql()` and `each_sql()` build `json_extract` and
+//! `json_each` queries from one. Those functions are only available
+//! if the linked sqlite has JSON1; check with `json1_available()` or
+//! `check_json1()` rather than waiting for "no such function" errors.
+//!
+//! ```rust,ignore
+//! conn.exec("create table doc (body text)")?;
+//! let mut ins = conn.prepare("insert into doc values ($1)")?;
+//! ins.update(&[&Json(vec!["a", "b"])])?;
+//!
+//! json::check_json1()?;
+//! let path = JsonPath::parse("$[1]")?;
+//! let mut stmt = conn.prepare(&json::extract_sql("doc", "body", &path))?;
+//! ```
+//!
+//! [JSON1]: https://www.sqlite.org/json1.html
+
+use std::ffi::CStr;
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use serde_json;
+
+use super::{SqliteError, SqliteErrorCode, SqliteResult};
+use core::{ColIx, ParamIx, PreparedStatement, ResultRow, str_charstar};
+use types::{FromSql, FromSqlRef, ToSql};
+use ffi;
+
+/// A value stored as JSON text.
+///
+/// `ToSql` binds the serialized text; `FromSql` parses column text,
+/// failing with `SQLITE_MISMATCH` if it isn't valid JSON for `T`.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Json<T>(pub T);
+
+impl<T: Serialize> ToSql for Json<T> {
+    fn to_sql(&self, s: &mut PreparedStatement, ix: ParamIx) -> SqliteResult<()> {
+        let text = try!(serde_json::to_string(&self.0).map_err(|err| {
+            SqliteError {
+                kind: SqliteErrorCode::SQLITE_MISMATCH,
+                desc: "can't serialize JSON",
+                detail: Some(format!("parameter {}: {}", ix, err)),
+            }
+        }));
+        s.bind_text(ix, &text)
+    }
+}
+
+impl<T: DeserializeOwned> FromSql for Json<T> {
+    fn from_sql(row: &ResultRow, col: ColIx) -> SqliteResult<Json<T>> {
+        let text: &str = try!(FromSqlRef::from_sql_ref(row, col));
+        serde_json::from_str(text).map(Json).map_err(|err| {
+            SqliteError {
+                kind: SqliteErrorCode::SQLITE_MISMATCH,
+                desc: "invalid JSON",
+                detail: Some(format!("column {}: {}", col, err)),
+            }
+        })
+    }
+}
+
+
+fn compileoption_used(name: &str) -> bool {
+    let c_name = str_charstar(name);
+    unsafe { ffi::sqlite3_compileoption_used(c_name.as_ptr()) != 0 }
+}
+
+/// Test whether the linked sqlite has the JSON1 functions.
+///
+/// They are built in as of sqlite 3.38.0 unless omitted with
+/// `SQLITE_OMIT_JSON`; before that, only with `SQLITE_ENABLE_JSON1`.
+///
+/// cf `sqlite3_compileoption_used`
+pub fn json1_available() -> bool {
+    compileoption_used("ENABLE_JSON1") ||
+    (unsafe { ffi::sqlite3_libversion_number() } >= 3038000 && !compileoption_used("OMIT_JSON"))
+}
+
+/// Like `json1_available()`, but give an error naming the sqlite
+/// version if JSON1 is missing.
+pub fn check_json1() -> SqliteResult<()> {
+    if json1_available() {
+        return Ok(());
+    }
+    let version = unsafe { CStr::from_ptr(ffi::sqlite3_libversion()) };
+    Err(SqliteError {
+        kind: SqliteErrorCode::SQLITE_ERROR,
+        desc: "sqlite was built without the JSON1 functions",
+        detail: Some(format!("sqlite {}", version.to_string_lossy())),
+    })
+}
+
+
+/// A JSON path expression, as used by `json_extract` and `json_each`.
+///
+/// The syntax is `$` followed by any number of `.key`, `."key"`,
+/// `[N]`, `[#]` or `[#-N]` steps.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct JsonPath {
+    path: String,
+}
+
+impl JsonPath {
+    /// The whole document, `$`.
+    pub fn root() -> JsonPath {
+        JsonPath { path: "$".to_string() }
+    }
+
+    /// Check the syntax of `path`.
+    pub fn parse(path: &str) -> SqliteResult<JsonPath> {
+        if path_syntax_ok(path) {
+            Ok(JsonPath { path: path.to_string() })
+        } else {
+            Err(SqliteError {
+                kind: SqliteErrorCode::SQLITE_MISUSE,
+                desc: "bad JSON path",
+                detail: Some(path.to_string()),
+            })
+        }
+    }
+
+    /// The path as text.
+    pub fn as_str(&self) -> &str {
+        &self.path
+    }
+}
+
+impl fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.path)
+    }
+}
+
+fn path_syntax_ok(path: &str) -> bool {
+    let mut rest = match path.strip_prefix('$') {
+        Some(rest) => rest,
+        None => return false,
+    };
+    while !rest.is_empty() {
+        rest = if let Some(quoted) = rest.strip_prefix(".\"") {
+            match quoted.find('"') {
+                Some(end) => &quoted[end + 1..],
+                None => return false,
+            }
+        } else if let Some(key) = rest.strip_prefix('.') {
+            let end = key.find(['.', '[']).unwrap_or(key.len());
+            if end == 0 {
+                return false;
+            }
+            &key[end..]
+        } else if let Some(index) = rest.strip_prefix('[') {
+            let end = match index.find(']') {
+                Some(end) => end,
+                None => return false,
+            };
+            let digits = match &index[..end] {
+                "#" => "0",
+                step => step.strip_prefix("#-").unwrap_or(step),
+            };
+            if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+                return false;
+            }
+            &index[end + 1..]
+        } else {
+            return false;
+        };
+    }
+    true
+}
+
+/// Quote an SQL identifier, e.g. a table or column name.
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Quote an SQL string literal.
+fn quote_literal(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+/// An SQL expression for the value at `path` in `column`, for use in
+/// `SELECT` lists and `WHERE` clauses.
+pub fn extract_expr(column: &str, path: &JsonPath) -> String {
+    format!("json_extract({}, {})",
+            quote_ident(column),
+            quote_literal(path.as_str()))
+}
+
+/// A query for the value at `path` in `column` of each row of `table`.
+///
+/// The query gives one column, `value`.
+pub fn extract_sql(table: &str, column: &str, path: &JsonPath) -> String {
+    format!("SELECT {} AS value FROM {}",
+            extract_expr(column, path),
+            quote_ident(table))
+}
+
+/// A query for the elements of the array or object at `path` in
+/// `column` of each row of `table`.
+///
+/// The query gives the columns `rowid` of `table` (so it must not be
+/// a `WITHOUT ROWID` table) and `key`, `value`, `type`, `fullkey` and
+/// `path` from `json_each`.
+pub fn each_sql(table: &str, column: &str, path: &JsonPath) -> String {
+    let table = quote_ident(table);
+    format!("SELECT {t}.rowid AS rowid, j.key, j.value, j.type, j.fullkey, j.path \
+             FROM {t}, json_each({t}.{c}, {p}) AS j",
+            t = table,
+            c = quote_ident(column),
+            p = quote_literal(path.as_str()))
+}
+
+// Local Variables:
+// flycheck-rust-crate-root: "lib.rs"
+// End:
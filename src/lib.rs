@@ -92,16 +92,34 @@ extern crate bitflags;
 #[macro_use]
 extern crate enum_primitive;
 
+#[cfg(feature = "derive")]
+extern crate sqlite3_derive;
+
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+
+#[cfg(feature = "json")]
+extern crate serde_json;
+
+/// Derive `FromRow` for a struct, reading fields from columns by name.
+///
+/// Field attributes: `#[sqlite3(rename = "column")]` and
+/// `#[sqlite3(default)]`; see the `rust-sqlite-derive` crate.
+#[cfg(feature = "derive")]
+pub use sqlite3_derive::FromRow;
+
 use std::error::Error;
 use std::fmt::Display;
 use std::fmt;
 
 pub use core::Access;
 pub use core::{DatabaseConnection, PreparedStatement, ResultSet, ResultRow};
-pub use core::{ColIx, ParamIx};
-pub use types::{FromSql, ToSql};
+pub use core::{ColIx, ColumnInfo, ParamIx};
+pub use types::{FromSql, FromSqlRef, ToSql, Value, ValueRef};
+pub use transaction::{RetryPolicy, Savepoint, Transaction, TransactionBehavior};
 
-use self::SqliteErrorCode::SQLITE_MISUSE;
+use self::SqliteErrorCode::{SQLITE_MISUSE, SQLITE_NOTFOUND, SQLITE_RANGE};
 
 pub mod core;
 pub mod types;
@@ -116,10 +134,27 @@ pub mod ffi;
 
 pub mod access;
 
+pub mod transaction;
+
+pub mod cache;
+
+pub mod blob;
+
+pub mod rows;
+
+#[cfg(feature = "serde")]
+pub mod serde_sql;
+
+#[cfg(feature = "json")]
+pub mod json;
+
 /// Mix in `update()` convenience function.
 pub trait StatementUpdate {
     /// Execute a statement after binding any parameters.
     fn update(&mut self, values: &[&ToSql]) -> SqliteResult<u64>;
+
+    /// Execute a statement after binding parameters by name.
+    fn update_named(&mut self, values: &[(&str, &ToSql)]) -> SqliteResult<u64>;
 }
 
 
@@ -135,21 +170,81 @@ impl StatementUpdate for core::PreparedStatement {
     ///
     /// [changes]: http://www.sqlite.org/c3ref/changes.html
     fn update(&mut self, values: &[&ToSql]) -> SqliteResult<u64> {
-        let check = {
-            try!(bind_values(self, values));
-            let mut results = self.execute();
-            match try!(results.step()) {
-                None => Ok(()),
-                Some(_row) => {
-                    Err(SqliteError {
-                        kind: SQLITE_MISUSE,
-                        desc: "unexpected SQLITE_ROW from update",
-                        detail: None,
-                    })
-                }
+        try!(bind_values(self, values));
+        update_bound(self)
+    }
+
+    /// Execute a statement after binding parameters by name.
+    ///
+    /// Names are looked up as in `PreparedStatement::parameter_index()`.
+    /// Fail with `Err(SQLITE_RANGE)` in case of an unknown name.
+    fn update_named(&mut self, values: &[(&str, &ToSql)]) -> SqliteResult<u64> {
+        try!(bind_named_values(self, values));
+        update_bound(self)
+    }
+}
+
+fn update_bound(stmt: &mut PreparedStatement) -> SqliteResult<u64> {
+    let check = {
+        let mut results = stmt.execute();
+        match try!(results.step()) {
+            None => Ok(()),
+            Some(_row) => {
+                Err(SqliteError {
+                    kind: SQLITE_MISUSE,
+                    desc: "unexpected SQLITE_ROW from update",
+                    detail: None,
+                })
             }
-        };
-        check.map(|_ok| self.changes())
+        }
+    };
+    check.map(|_ok| stmt.changes())
+}
+
+
+/// Mix in `execute_many()` convenience function.
+pub trait StatementExecuteMany {
+    /// Execute a statement once for each set of parameters.
+    fn execute_many<'v, I, P>(&mut self, rows: I, batch_size: usize) -> SqliteResult<u64>
+        where I: IntoIterator<Item = P>,
+              P: AsRef<[&'v ToSql]>;
+}
+
+impl StatementExecuteMany for core::PreparedStatement {
+    /// Execute a statement once for each set of parameters, e.g. for
+    /// bulk inserts, and report the total number of rows modified.
+    ///
+    /// Bindings are cleared between rows. Unless a transaction is
+    /// already open on the connection, rows are grouped into
+    /// transactions of `batch_size` rows (all in one if `batch_size`
+    /// is 0). On error, the transaction of the failing row is rolled
+    /// back, but earlier ones stay committed; the error `detail` gives
+    /// the index of the failing row.
+    fn execute_many<'v, I, P>(&mut self, rows: I, batch_size: usize) -> SqliteResult<u64>
+        where I: IntoIterator<Item = P>,
+              P: AsRef<[&'v ToSql]>
+    {
+        let batch_size = if batch_size == 0 { usize::MAX } else { batch_size };
+        let mut rows = rows.into_iter().enumerate().peekable();
+        let mut total = 0;
+        while rows.peek().is_some() {
+            let tx = try!(self.begin_unless_open());
+            for (ix, values) in rows.by_ref().take(batch_size) {
+                self.clear_bindings();
+                let changes = bind_values(self, values.as_ref()).and_then(|()| update_bound(self));
+                total += try!(changes.map_err(|err| {
+                    let detail = match err.detail {
+                        Some(msg) => format!("row {}: {}", ix, msg),
+                        None => format!("row {}", ix),
+                    };
+                    SqliteError { detail: Some(detail), ..err }
+                }));
+            }
+            if let Some(tx) = tx {
+                try!(tx.commit());
+            }
+        }
+        Ok(total)
     }
 }
 
@@ -160,6 +255,12 @@ pub trait QueryEach<F>
 {
     /// Process rows from a query after binding parameters.
     fn query_each(&mut self, values: &[&ToSql], each_row: &mut F) -> SqliteResult<()>;
+
+    /// Process rows from a query after binding parameters by name.
+    fn query_each_named(&mut self,
+                        values: &[(&str, &ToSql)],
+                        each_row: &mut F)
+                        -> SqliteResult<()>;
 }
 
 impl<F> QueryEach<F> for core::PreparedStatement
@@ -171,15 +272,29 @@ impl<F> QueryEach<F> for core::PreparedStatement
     /// exiting on `Err`.
     fn query_each(&mut self, values: &[&ToSql], each_row: &mut F) -> SqliteResult<()> {
         try!(bind_values(self, values));
-        let mut results = self.execute();
-        loop {
-            match try!(results.step()) {
-                None => break,
-                Some(ref mut row) => try!(each_row(row)),
-            }
+        each_bound(self, each_row)
+    }
+
+    fn query_each_named(&mut self,
+                        values: &[(&str, &ToSql)],
+                        each_row: &mut F)
+                        -> SqliteResult<()> {
+        try!(bind_named_values(self, values));
+        each_bound(self, each_row)
+    }
+}
+
+fn each_bound<F>(stmt: &mut PreparedStatement, each_row: &mut F) -> SqliteResult<()>
+    where F: FnMut(&mut ResultRow) -> SqliteResult<()>
+{
+    let mut results = stmt.execute();
+    loop {
+        match try!(results.step()) {
+            None => break,
+            Some(ref mut row) => try!(each_row(row)),
         }
-        Ok(())
     }
+    Ok(())
 }
 
 
@@ -189,6 +304,13 @@ pub trait QueryFold<F, A>
 {
     /// Fold rows from a query after binding parameters.
     fn query_fold(&mut self, values: &[&ToSql], init: A, each_row: F) -> SqliteResult<A>;
+
+    /// Fold rows from a query after binding parameters by name.
+    fn query_fold_named(&mut self,
+                        values: &[(&str, &ToSql)],
+                        init: A,
+                        each_row: F)
+                        -> SqliteResult<A>;
 }
 
 
@@ -197,16 +319,94 @@ impl<F, A> QueryFold<F, A> for core::PreparedStatement
 {
     /// Fold rows from a query after binding parameters.
     fn query_fold(&mut self, values: &[&ToSql], init: A, f: F) -> SqliteResult<A> {
+        try!(bind_values(self, values));
+        fold_bound(self, init, f)
+    }
+
+    fn query_fold_named(&mut self,
+                        values: &[(&str, &ToSql)],
+                        init: A,
+                        f: F)
+                        -> SqliteResult<A> {
+        try!(bind_named_values(self, values));
+        fold_bound(self, init, f)
+    }
+}
+
+fn fold_bound<F, A>(stmt: &mut PreparedStatement, init: A, f: F) -> SqliteResult<A>
+    where F: Fn(&mut ResultRow, A) -> SqliteResult<A>
+{
+    let mut results = stmt.execute();
+    let mut accum = init;
+    loop {
+        match try!(results.step()) {
+            None => break,
+            Some(ref mut row) => accum = try!(f(row, accum)),
+        }
+    }
+    Ok(accum)
+}
+
+
+/// Mix in `query_row()` and `query_opt_row()` convenience functions.
+pub trait QueryRow {
+    /// Compute a value from the one row of a query after binding
+    /// parameters.
+    fn query_row<F, T>(&mut self, values: &[&ToSql], f: F) -> SqliteResult<T>
+        where F: FnOnce(&mut ResultRow) -> SqliteResult<T>;
+
+    /// Compute a value from the row, if any, of a query after binding
+    /// parameters.
+    fn query_opt_row<F, T>(&mut self, values: &[&ToSql], f: F) -> SqliteResult<Option<T>>
+        where F: FnOnce(&mut ResultRow) -> SqliteResult<T>;
+}
+
+impl QueryRow for core::PreparedStatement {
+    /// Compute a value from the one row of a query after binding
+    /// parameters.
+    ///
+    /// Fail with an error `desc`ribed as `NO_ROWS` if there are no
+    /// rows and as `TOO_MANY_ROWS` if there is more than one; cf
+    /// `SqliteError::is_no_rows()` and `SqliteError::is_too_many_rows()`.
+    fn query_row<F, T>(&mut self, values: &[&ToSql], f: F) -> SqliteResult<T>
+        where F: FnOnce(&mut ResultRow) -> SqliteResult<T>
+    {
+        match try!(self.query_opt_row(values, f)) {
+            Some(value) => Ok(value),
+            None => {
+                Err(SqliteError {
+                    kind: SQLITE_NOTFOUND,
+                    desc: NO_ROWS,
+                    detail: None,
+                })
+            }
+        }
+    }
+
+    /// Compute a value from the row, if any, of a query after binding
+    /// parameters.
+    ///
+    /// Fail with an error `desc`ribed as `TOO_MANY_ROWS` if there is
+    /// more than one row.
+    fn query_opt_row<F, T>(&mut self, values: &[&ToSql], f: F) -> SqliteResult<Option<T>>
+        where F: FnOnce(&mut ResultRow) -> SqliteResult<T>
+    {
         try!(bind_values(self, values));
         let mut results = self.execute();
-        let mut accum = init;
-        loop {
-            match try!(results.step()) {
-                None => break,
-                Some(ref mut row) => accum = try!(f(row, accum)),
+        let value = match try!(results.step()) {
+            None => return Ok(None),
+            Some(ref mut row) => try!(f(row)),
+        };
+        match try!(results.step()) {
+            None => Ok(Some(value)),
+            Some(_row) => {
+                Err(SqliteError {
+                    kind: SQLITE_MISUSE,
+                    desc: TOO_MANY_ROWS,
+                    detail: None,
+                })
             }
         }
-        Ok(accum)
     }
 }
 
@@ -221,11 +421,18 @@ pub trait Query<F, T>
     /// and the statement is executed.
     ///
     /// Returns an iterator over rows transformed by `txform`,
-    /// which computes a value for each row (or an error).
+    /// which computes a value for each row (or an error). To build
+    /// any `T: FromRow`, pass `T::from_row`.
     fn query<'stmt>(&'stmt mut self,
                     values: &[&ToSql],
                     txform: F)
                     -> SqliteResult<QueryResults<'stmt, T, F>>;
+
+    /// Iterate over query results after binding parameters by name.
+    fn query_named<'stmt>(&'stmt mut self,
+                          values: &[(&str, &ToSql)],
+                          txform: F)
+                          -> SqliteResult<QueryResults<'stmt, T, F>>;
 }
 
 impl<F, T> Query<F, T> for core::PreparedStatement
@@ -242,8 +449,70 @@ impl<F, T> Query<F, T> for core::PreparedStatement
             txform: txform,
         })
     }
+
+    fn query_named<'stmt>(&'stmt mut self,
+                          values: &[(&str, &ToSql)],
+                          txform: F)
+                          -> SqliteResult<QueryResults<'stmt, T, F>> {
+        try!(bind_named_values(self, values));
+        let results = self.execute();
+        Ok(QueryResults {
+            results: results,
+            txform: txform,
+        })
+    }
+}
+
+/// Types that can be built from a whole result row.
+///
+/// With the `derive` feature, `#[derive(FromRow)]` implements this
+/// for structs, reading each field from the column of the same name.
+///
+/// `from_row` takes a row just as the transformations given to
+/// `Query::query()` and the like do, so `T::from_row` can be passed
+/// in place of a closure, e.g. `stmt.query(&[], Person::from_row)`.
+pub trait FromRow: Sized {
+    /// Build a value from the columns of `row`.
+    fn from_row(row: &mut ResultRow) -> SqliteResult<Self>;
 }
 
+/// Build tuples by position, e.g. `(i64, String)` from the first two
+/// columns, failing with `SQLITE_RANGE` if there are too few.
+macro_rules! tuple_from_row {
+    ($arity:expr; $($t:ident $ix:expr),+) => {
+        impl<$($t: FromSql),+> FromRow for ($($t,)+) {
+            fn from_row(row: &mut ResultRow) -> SqliteResult<Self> {
+                let count = row.column_count();
+                if count < $arity {
+                    return Err(SqliteError {
+                        kind: SQLITE_RANGE,
+                        desc: "row has too few columns",
+                        detail: Some(format!("{} columns for a tuple of {}", count, $arity)),
+                    });
+                }
+                Ok(($(try!(<$t>::from_sql(row, $ix)),)+))
+            }
+        }
+    }
+}
+
+tuple_from_row!(1; A 0);
+tuple_from_row!(2; A 0, B 1);
+tuple_from_row!(3; A 0, B 1, C 2);
+tuple_from_row!(4; A 0, B 1, C 2, D 3);
+tuple_from_row!(5; A 0, B 1, C 2, D 3, E 4);
+tuple_from_row!(6; A 0, B 1, C 2, D 3, E 4, F 5);
+tuple_from_row!(7; A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+tuple_from_row!(8; A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+tuple_from_row!(9; A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8);
+tuple_from_row!(10; A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9);
+tuple_from_row!(11; A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10);
+tuple_from_row!(12; A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10, L 11);
+tuple_from_row!(13; A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10, L 11, M 12);
+tuple_from_row!(14; A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10, L 11, M 12, N 13);
+tuple_from_row!(15; A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10, L 11, M 12, N 13, O 14);
+tuple_from_row!(16; A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10, L 11, M 12, N 13, O 14, P 15);
+
 /// An iterator over transformed query results
 pub struct QueryResults<'stmt, T, F>
     where F: FnMut(&mut ResultRow) -> SqliteResult<T>
@@ -275,22 +544,42 @@ fn bind_values(s: &mut PreparedStatement, values: &[&ToSql]) -> SqliteResult<()>
     Ok(())
 }
 
+fn bind_named_values(s: &mut PreparedStatement, values: &[(&str, &ToSql)]) -> SqliteResult<()> {
+    for &(name, v) in values.iter() {
+        match s.parameter_index(name) {
+            Some(p) => try!(v.to_sql(s, p)),
+            None => {
+                return Err(SqliteError {
+                    kind: SQLITE_RANGE,
+                    desc: "no such parameter name",
+                    detail: Some(name.to_string()),
+                })
+            }
+        }
+    }
+    Ok(())
+}
+
 
 /// Access result columns of a row by name or numeric index.
+///
+/// Values may borrow from the row, e.g. `&str`; cf `FromSqlRef`.
 pub trait ResultRowAccess {
     /// Get `T` type result value from `idx`th column of a row.
     ///
     /// # Panic
     ///
     /// Panics if there is no such column or value.
-    fn get<I: RowIndex + Display + Clone, T: FromSql>(&mut self, idx: I) -> T;
+    fn get<'a, I: RowIndex + Display + Clone, T: FromSqlRef<'a>>(&'a self, idx: I) -> T;
 
     /// Try to get `T` type result value from `idx`th column of a row.
-    fn get_opt<I: RowIndex + Display + Clone, T: FromSql>(&mut self, idx: I) -> SqliteResult<T>;
+    fn get_opt<'a, I: RowIndex + Display + Clone, T: FromSqlRef<'a>>(&'a self,
+                                                                      idx: I)
+                                                                      -> SqliteResult<T>;
 }
 
 impl<'res, 'row> ResultRowAccess for core::ResultRow<'res, 'row> {
-    fn get<I: RowIndex + Display + Clone, T: FromSql>(&mut self, idx: I) -> T {
+    fn get<'a, I: RowIndex + Display + Clone, T: FromSqlRef<'a>>(&'a self, idx: I) -> T {
         match self.get_opt(idx.clone()) {
             Ok(ok) => ok,
             Err(err) => {
@@ -301,9 +590,11 @@ impl<'res, 'row> ResultRowAccess for core::ResultRow<'res, 'row> {
         }
     }
 
-    fn get_opt<I: RowIndex + Display + Clone, T: FromSql>(&mut self, idx: I) -> SqliteResult<T> {
+    fn get_opt<'a, I: RowIndex + Display + Clone, T: FromSqlRef<'a>>(&'a self,
+                                                                      idx: I)
+                                                                      -> SqliteResult<T> {
         match idx.idx(self) {
-            Some(idx) => FromSql::from_sql(self, idx),
+            Some(idx) => FromSqlRef::from_sql_ref(self, idx),
             None => {
                 Err(SqliteError {
                     kind: SQLITE_MISUSE,
@@ -321,12 +612,15 @@ impl<'res, 'row> ResultRowAccess for core::ResultRow<'res, 'row> {
 /// [RowIndex]: http://www.rust-ci.org/sfackler/rust-postgres/doc/postgres/trait.RowIndex.html
 pub trait RowIndex {
     /// Try to convert `self` to an index into a row.
-    fn idx(&self, row: &mut ResultRow) -> Option<ColIx>;
+    ///
+    /// Note `row` is borrowed immutably, as by `ResultRowAccess::get()`;
+    /// implementations written for `&mut ResultRow` need updating.
+    fn idx(&self, row: &ResultRow) -> Option<ColIx>;
 }
 
 impl RowIndex for ColIx {
     /// Index into a row directly by uint.
-    fn idx(&self, _row: &mut ResultRow) -> Option<ColIx> {
+    fn idx(&self, _row: &ResultRow) -> Option<ColIx> {
         Some(*self)
     }
 }
@@ -336,7 +630,7 @@ impl RowIndex for &'static str {
     ///
     /// *TODO: figure out how to use lifetime of row rather than
     /// `static`.*
-    fn idx(&self, row: &mut ResultRow) -> Option<ColIx> {
+    fn idx(&self, row: &ResultRow) -> Option<ColIx> {
         let mut ixs = 0..row.column_count();
         ixs.find(|ix| row.with_column_name(*ix, false, |name| name == *self))
     }
@@ -413,11 +707,31 @@ impl Display for SqliteError {
     }
 }
 
+/// `desc` of the `SQLITE_NOTFOUND` error from `QueryRow::query_row()`
+/// when the query returns no rows.
+pub const NO_ROWS: &'static str = "query returned no rows";
+
+/// `desc` of the `SQLITE_MISUSE` error from `QueryRow::query_row()`
+/// and `QueryRow::query_opt_row()` when the query returns more than
+/// one row.
+pub const TOO_MANY_ROWS: &'static str = "query returned more than one row";
+
 impl SqliteError {
     /// Get a detailed description of the error
     pub fn detail(&self) -> Option<String> {
         self.detail.clone()
     }
+
+    /// Whether `query_row()` failed because the query returned no rows.
+    pub fn is_no_rows(&self) -> bool {
+        self.kind == SQLITE_NOTFOUND && self.desc == NO_ROWS
+    }
+
+    /// Whether `query_row()` or `query_opt_row()` failed because the
+    /// query returned more than one row.
+    pub fn is_too_many_rows(&self) -> bool {
+        self.kind == SQLITE_MISUSE && self.desc == TOO_MANY_ROWS
+    }
 }
 
 impl Error for SqliteError {
@@ -536,6 +850,132 @@ mod bind_tests {
         assert_eq!(go(), Ok((2, 3)))
     }
 
+    #[test]
+    fn bind_by_name() {
+        use super::{QueryFold, StatementUpdate};
+
+        let mut db = DatabaseConnection::in_memory().unwrap();
+        db.exec("CREATE TABLE test (id int, name text)").unwrap();
+        {
+            let mut tx = db.prepare("INSERT INTO test (id, name) VALUES (:id, @name)").unwrap();
+            assert_eq!(tx.parameter_index(":id"), Some(1));
+            assert_eq!(tx.parameter_index("name"), Some(2));
+            assert_eq!(tx.parameter_index("$id"), None);
+            assert_eq!(tx.parameter_name(2), Some("@name".to_string()));
+            assert_eq!(tx.update_named(&[("name", &"Jane".to_string()), (":id", &2)]),
+                       Ok(1));
+
+            let oops = tx.update_named(&[("nickname", &"J".to_string())]).err().unwrap();
+            assert_eq!(oops.kind, super::SqliteErrorCode::SQLITE_RANGE);
+            assert_eq!(oops.detail(), Some("nickname".to_string()));
+        }
+        let mut q = db.prepare("SELECT name FROM test WHERE id = $id").unwrap();
+        let names = q.query_fold_named(&[("$id", &2)], vec![], |row, mut names| {
+                names.push(row.get::<u32, String>(0));
+                Ok(names)
+            })
+            .unwrap();
+        assert_eq!(names, vec!["Jane".to_string()]);
+    }
+
+    #[test]
+    fn execute_many_rows() {
+        use super::{StatementExecuteMany, ToSql};
+
+        let mut db = DatabaseConnection::in_memory().unwrap();
+        db.exec("CREATE TABLE test (id int primary key, name text)").unwrap();
+        let names = ["a", "b", "c", "d", "e"].iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        {
+            let mut ins = db.prepare("INSERT INTO test (id, name) VALUES ($1, $2)").unwrap();
+            let ids: Vec<i32> = (0..5).collect();
+            let rows = ids.iter().zip(names.iter()).map(|(id, name)| {
+                let row: Vec<&ToSql> = vec![id, name];
+                row
+            });
+            assert_eq!(ins.execute_many(rows, 2), Ok(5));
+
+            // batch 5, 6, 7 is committed; row 4 (1) is a duplicate,
+            // so batch 8, 1, 9 rolls back
+            let rows: Vec<[&ToSql; 1]> = vec![[&5], [&6], [&7], [&8], [&1], [&9]];
+            let mut ins = db.prepare("INSERT INTO test (id) VALUES ($1)").unwrap();
+            let err = ins.execute_many(rows, 3).err().unwrap();
+            assert_eq!(err.kind, super::SqliteErrorCode::SQLITE_CONSTRAINT);
+            assert_eq!(err.detail(),
+                       Some("row 4: UNIQUE constraint failed: test.id".to_string()));
+            assert!(db.get_autocommit());
+        }
+        let mut count = db.prepare("SELECT count(*) FROM test").unwrap();
+        let mut rows = count.execute();
+        assert_eq!(rows.step().unwrap().unwrap().column_int(0), 8);
+    }
+
+    #[test]
+    fn query_one_row() {
+        use super::{QueryRow, ResultRow};
+        use super::SqliteErrorCode::{SQLITE_MISUSE, SQLITE_NOTFOUND};
+
+        let db = DatabaseConnection::in_memory().unwrap();
+        let sql = "SELECT n FROM (SELECT 1 AS n UNION ALL SELECT 2) WHERE n <= $1";
+        let mut stmt = db.prepare(sql).unwrap();
+        let get = |row: &mut ResultRow| Ok(row.column_int(0));
+        assert_eq!(stmt.query_row(&[&1], get), Ok(1));
+        let none = stmt.query_row(&[&0], get).err().unwrap();
+        assert_eq!((none.kind, none.desc), (SQLITE_NOTFOUND, super::NO_ROWS));
+        assert!(none.is_no_rows() && !none.is_too_many_rows());
+        let many = stmt.query_row(&[&2], get).err().unwrap();
+        assert_eq!((many.kind, many.desc), (SQLITE_MISUSE, super::TOO_MANY_ROWS));
+        assert!(many.is_too_many_rows() && !many.is_no_rows());
+        assert_eq!(stmt.query_opt_row(&[&0], get), Ok(None));
+
+        // errors from the closure itself are told apart
+        let typo = stmt.query_row(&[&1], |row| row.get_opt::<&str, i32>("m")).err().unwrap();
+        assert_eq!(typo.kind, SQLITE_MISUSE);
+        assert!(!typo.is_too_many_rows() && !typo.is_no_rows());
+        let notfound = stmt.query_row(&[&1], |_| -> SqliteResult<i32> {
+                Err(super::SqliteError {
+                    kind: SQLITE_NOTFOUND,
+                    desc: "no such thing",
+                    detail: None,
+                })
+            })
+            .err()
+            .unwrap();
+        assert!(!notfound.is_no_rows());
+
+        assert_eq!(db.query_row(sql, &[&1], |row| Ok(row.get::<u32, String>(0))),
+                   Ok("1".to_string()));
+        assert_eq!(db.query_opt_row(sql, &[&1], get), Ok(Some(1)));
+        assert!(db.query_opt_row(sql, &[&5], get).is_err());
+    }
+
+    #[test]
+    fn query_tuples() {
+        use super::{FromRow, Query};
+
+        let db = DatabaseConnection::in_memory().unwrap();
+        let mut stmt = db.prepare("SELECT 1, 'one', x'01' UNION ALL SELECT 2, 'two', NULL")
+            .unwrap();
+        let rows = stmt.query(&[], <(i64, String, Option<Vec<u8>>)>::from_row)
+            .unwrap()
+            .collect::<SqliteResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(rows,
+                   vec![(1, "one".to_string(), Some(vec![1])), (2, "two".to_string(), None)]);
+
+        let mut rows = stmt.query(&[], <(i32,)>::from_row).unwrap();
+        assert_eq!(rows.next(), Some(Ok((1,))));
+
+        let mut stmt = db.prepare("SELECT 1, 2").unwrap();
+        let err = stmt.query(&[], <(i32, i32, i32)>::from_row)
+            .unwrap()
+            .next()
+            .unwrap()
+            .err()
+            .unwrap();
+        assert_eq!(err.kind, super::SqliteErrorCode::SQLITE_RANGE);
+        assert_eq!(err.detail(), Some("2 columns for a tuple of 3".to_string()));
+    }
+
     #[test]
     fn err_with_detail() {
         let io = || {
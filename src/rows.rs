@@ -0,0 +1,340 @@
+//! Iterator-style access to query results.
+//!
+//! A `ResultRow` borrows from its `ResultSet` and is only valid until
+//! the next step, so a `ResultSet` can't be an `Iterator`. Instead,
+//! the `RowStream` trait provides a "lending" `next_row()` along with
+//! `filter()` and `take()` combinators that keep that constraint,
+//! and `map()` (or `owned()`), which turns the stream into an ordinary
+//! `Iterator` over values that don't borrow from the row.
+//!
+//! ```rust
+//! extern crate sqlite3;
+//!
+//! use sqlite3::{DatabaseConnection, ResultRowAccess};
+//! use sqlite3::rows::RowStream;
+//!
+//! pub fn main() {
+//!     let conn = DatabaseConnection::in_memory().unwrap();
+//!     let mut stmt = conn.prepare("select 1 union all select 2 union all select 3").unwrap();
+//!     let odd: Vec<i32> = stmt.execute()
+//!         .filter(|row| row.column_int(0) % 2 == 1)
+//!         .map(|row| Ok(row.get(0u32)))
+//!         .collect::<Result<_, _>>()
+//!         .unwrap();
+//!     assert_eq!(odd, vec![1, 3]);
+//! }
+//! ```
+
+use super::{ColumnType, SqliteResult};
+use core::{ColIx, ResultRow, ResultSet};
+use types::Value;
+
+/// A source of rows that can be stepped through one at a time.
+pub trait RowStream<'res> {
+    /// Advance to the next row, if any.
+    fn next_row<'a>(&'a mut self) -> SqliteResult<Option<ResultRow<'res, 'a>>> where 'res: 'a;
+
+    /// Get the row produced by the latest `next_row()` again, if any.
+    fn current_row<'a>(&'a mut self) -> Option<ResultRow<'res, 'a>> where 'res: 'a;
+
+    /// Skip rows for which `predicate` is false.
+    fn filter<P>(self, predicate: P) -> Filter<Self, P>
+        where Self: Sized,
+              P: FnMut(&ResultRow) -> bool
+    {
+        Filter {
+            rows: self,
+            predicate: predicate,
+        }
+    }
+
+    /// Stop after at most `n` rows.
+    fn take(self, n: usize) -> Take<Self>
+        where Self: Sized
+    {
+        Take {
+            rows: self,
+            remaining: n,
+            done: false,
+        }
+    }
+
+    /// Compute a value from each row, giving an `Iterator`.
+    fn map<F, T>(self, f: F) -> Map<Self, F>
+        where Self: Sized,
+              F: FnMut(&mut ResultRow) -> SqliteResult<T>
+    {
+        Map { rows: self, f: f }
+    }
+
+    /// Copy each row, giving an `Iterator` over `OwnedRow`s.
+    fn owned(self) -> Map<Self, fn(&mut ResultRow) -> SqliteResult<OwnedRow>>
+        where Self: Sized
+    {
+        fn snapshot(row: &mut ResultRow) -> SqliteResult<OwnedRow> {
+            Ok(OwnedRow::from_row(row))
+        }
+        self.map(snapshot)
+    }
+}
+
+impl<'res> RowStream<'res> for ResultSet<'res> {
+    fn next_row<'a>(&'a mut self) -> SqliteResult<Option<ResultRow<'res, 'a>>>
+        where 'res: 'a
+    {
+        self.step()
+    }
+
+    fn current_row<'a>(&'a mut self) -> Option<ResultRow<'res, 'a>>
+        where 'res: 'a
+    {
+        ResultSet::current_row(self)
+    }
+}
+
+/// Rows that satisfy a predicate; cf `RowStream::filter()`.
+pub struct Filter<S, P> {
+    rows: S,
+    predicate: P,
+}
+
+impl<'res, S, P> RowStream<'res> for Filter<S, P>
+    where S: RowStream<'res>,
+          P: FnMut(&ResultRow) -> bool
+{
+    fn next_row<'a>(&'a mut self) -> SqliteResult<Option<ResultRow<'res, 'a>>>
+        where 'res: 'a
+    {
+        loop {
+            let keep = match try!(self.rows.next_row()) {
+                None => return Ok(None),
+                Some(row) => (self.predicate)(&row),
+            };
+            if keep {
+                return Ok(self.rows.current_row());
+            }
+        }
+    }
+
+    fn current_row<'a>(&'a mut self) -> Option<ResultRow<'res, 'a>>
+        where 'res: 'a
+    {
+        self.rows.current_row()
+    }
+}
+
+/// A limited number of rows; cf `RowStream::take()`.
+pub struct Take<S> {
+    rows: S,
+    remaining: usize,
+    // whether `next_row()` has returned `None`
+    done: bool,
+}
+
+impl<'res, S> RowStream<'res> for Take<S>
+    where S: RowStream<'res>
+{
+    fn next_row<'a>(&'a mut self) -> SqliteResult<Option<ResultRow<'res, 'a>>>
+        where 'res: 'a
+    {
+        if self.remaining == 0 {
+            self.done = true;
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        let row = try!(self.rows.next_row());
+        self.done = row.is_none();
+        Ok(row)
+    }
+
+    fn current_row<'a>(&'a mut self) -> Option<ResultRow<'res, 'a>>
+        where 'res: 'a
+    {
+        if self.done {
+            None
+        } else {
+            self.rows.current_row()
+        }
+    }
+}
+
+/// An `Iterator` over values computed from rows; cf `RowStream::map()`.
+pub struct Map<S, F> {
+    rows: S,
+    f: F,
+}
+
+impl<'res, S, F, T> Iterator for Map<S, F>
+    where S: RowStream<'res>,
+          F: FnMut(&mut ResultRow) -> SqliteResult<T>
+{
+    type Item = SqliteResult<T>;
+
+    fn next(&mut self) -> Option<SqliteResult<T>> {
+        match self.rows.next_row() {
+            Ok(None) => None,
+            Ok(Some(ref mut row)) => Some((self.f)(row)),
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+/// A copy of all the columns of a row, independent of the statement
+/// it came from.
+#[derive(Debug, PartialEq, Clone)]
+pub struct OwnedRow {
+    names: Vec<String>,
+    values: Vec<Value>,
+}
+
+impl OwnedRow {
+    /// Copy the names and values of all columns of `row`.
+    ///
+    /// Text that is not valid UTF-8 is copied as a blob; cf
+    /// `ResultRow::column_value()`.
+    pub fn from_row(row: &mut ResultRow) -> OwnedRow {
+        let names = (0..row.column_count())
+            .map(|ix| row.with_column_name(ix, String::new(), |name| name.to_string()))
+            .collect();
+        OwnedRow {
+            names: names,
+            values: row.values(),
+        }
+    }
+
+    /// Number of columns.
+    pub fn column_count(&self) -> ColIx {
+        self.values.len() as ColIx
+    }
+
+    /// Values of the columns.
+    pub fn values(&self) -> &[Value] {
+        &self.values
+    }
+
+    /// Names of the columns.
+    pub fn column_names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Find a column by name.
+    pub fn column_index(&self, name: &str) -> Option<ColIx> {
+        self.names.iter().position(|n| n == name).map(|ix| ix as ColIx)
+    }
+
+    fn value(&self, col: ColIx) -> &Value {
+        self.values.get(col as usize).unwrap_or(&Value::Null)
+    }
+
+    /// Look up the type of a column.
+    ///
+    /// Return `SQLITE_NULL` if there is no such `col`.
+    pub fn column_type(&self, col: ColIx) -> ColumnType {
+        self.value(col).column_type()
+    }
+
+    /// Get the integer value of a column, or `None` if it isn't one.
+    pub fn column_int64(&self, col: ColIx) -> Option<i64> {
+        match *self.value(col) {
+            Value::Integer(i) => Some(i),
+            _ => None,
+        }
+    }
+
+    /// Get the floating point value of a column, or `None` if it isn't one.
+    pub fn column_double(&self, col: ColIx) -> Option<f64> {
+        match *self.value(col) {
+            Value::Real(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    /// Get the text value of a column, or `None` if it isn't text.
+    pub fn column_str(&self, col: ColIx) -> Option<&str> {
+        match *self.value(col) {
+            Value::Text(ref s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Get the blob value of a column, or `None` if it isn't a blob.
+    pub fn column_slice(&self, col: ColIx) -> Option<&[u8]> {
+        match *self.value(col) {
+            Value::Blob(ref b) => Some(b),
+            _ => None,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::RowStream;
+    use core::DatabaseConnection;
+    use std::cmp::Reverse;
+    use super::super::ColumnType::{SQLITE_INTEGER, SQLITE_NULL, SQLITE_TEXT};
+
+    const SQL: &'static str = "select 1 as n, 'one' as s
+                               union all select 2, null
+                               union all select 3, 'three'
+                               union all select 4, x'00'";
+
+    #[test]
+    fn filter_take_map() {
+        let db = DatabaseConnection::in_memory().unwrap();
+        let mut stmt = db.prepare(SQL).unwrap();
+        let ns: Vec<i32> = stmt.execute()
+            .filter(|row| row.column_int(0) != 1)
+            .take(2)
+            .map(|row| Ok(row.column_int(0)))
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(ns, vec![2, 3]);
+    }
+
+    #[test]
+    fn lending() {
+        let db = DatabaseConnection::in_memory().unwrap();
+        let mut stmt = db.prepare(SQL).unwrap();
+        let mut rows = stmt.execute().filter(|row| row.column_type(1) == SQLITE_TEXT);
+        let mut seen = vec![];
+        while let Some(row) = rows.next_row().unwrap() {
+            seen.push(row.column_str(1).unwrap().to_string());
+        }
+        assert_eq!(seen, vec!["one".to_string(), "three".to_string()]);
+        assert!(rows.current_row().is_none());
+    }
+
+    #[test]
+    fn take_exhausted() {
+        let db = DatabaseConnection::in_memory().unwrap();
+        let mut stmt = db.prepare(SQL).unwrap();
+        let mut rows = stmt.execute().take(1);
+        assert_eq!(rows.next_row().unwrap().unwrap().column_int(0), 1);
+        assert_eq!(rows.current_row().unwrap().column_int(0), 1);
+        assert!(rows.next_row().unwrap().is_none());
+        assert!(rows.current_row().is_none());
+    }
+
+    #[test]
+    fn owned_rows() {
+        let db = DatabaseConnection::in_memory().unwrap();
+        let mut stmt = db.prepare(SQL).unwrap();
+        let mut rows = stmt.execute().owned().collect::<Result<Vec<_>, _>>().unwrap();
+        rows.sort_by_key(|row| Reverse(row.column_int64(0)));
+
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[0].column_names(), &["n".to_string(), "s".to_string()]);
+        assert_eq!(rows[0].column_slice(1), Some(&[0u8][..]));
+        assert_eq!(rows[1].column_index("s"), Some(1));
+        assert_eq!(rows[1].column_type(1), SQLITE_TEXT);
+        assert_eq!(rows[1].column_str(1), Some("three"));
+        assert_eq!(rows[2].column_type(0), SQLITE_INTEGER);
+        assert_eq!(rows[2].column_type(1), SQLITE_NULL);
+        assert_eq!(rows[3].column_int64(0), Some(1));
+    }
+}
+
+// Local Variables:
+// flycheck-rust-crate-root: "lib.rs"
+// End:
@@ -0,0 +1,599 @@
+//! Binding and reading structs with [serde][].
+//!
+//! With the `serde` feature, `bind_named()` binds the fields of any
+//! `Serialize` struct (or map) to the statement parameters of the same
+//! name, e.g. `:name`, and `from_row()` builds any `Deserialize` value
+//! from the columns of a row, matching struct fields to column names.
+//! Values go through `types::Value`, so they are stored just as the
+//! corresponding `ToSql` types are.
+//!
+//! ```rust,ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Person {
+//!     id: i64,
+//!     name: String,
+//! }
+//!
+//! let mut ins = conn.prepare("insert into person (id, name) values (:id, :name)")?;
+//! serde_sql::bind_named(&mut ins, &Person { id: 1, name: "Dan".to_string() })?;
+//! ins.execute().step()?;
+//!
+//! let mut stmt = conn.prepare("select * from person")?;
+//! let mut rows = stmt.execute();
+//! while let Some(row) = rows.step()? {
+//!     let person: Person = serde_sql::from_row(&row)?;
+//! }
+//! ```
+//!
+//! [serde]: https://serde.rs/
+
+use std::fmt::Display;
+
+use serde::de::{self, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{self, Impossible, Serialize};
+
+use super::{SqliteError, SqliteErrorCode, SqliteResult, ToSql};
+use core::{ColIx, PreparedStatement, ResultRow};
+use types::{Value, ValueRef};
+
+impl ser::Error for SqliteError {
+    fn custom<T: Display>(msg: T) -> SqliteError {
+        SqliteError {
+            kind: SqliteErrorCode::SQLITE_MISMATCH,
+            desc: "serde error",
+            detail: Some(msg.to_string()),
+        }
+    }
+}
+
+impl de::Error for SqliteError {
+    fn custom<T: Display>(msg: T) -> SqliteError {
+        <SqliteError as ser::Error>::custom(msg)
+    }
+}
+
+fn unsupported(what: &str) -> SqliteError {
+    SqliteError {
+        kind: SqliteErrorCode::SQLITE_MISMATCH,
+        desc: "unsupported type for serde binding",
+        detail: Some(what.to_string()),
+    }
+}
+
+/// Bind the fields of `value`, a struct or a map with string keys, to
+/// the parameters of the same names.
+///
+/// Bindings from before are cleared first, so parameters `value`
+/// doesn't cover are `NULL`. Names are looked up as in
+/// `PreparedStatement::parameter_index()`; a field with no such
+/// parameter fails with `SQLITE_RANGE`, as in `query_named()`. Field
+/// values must be scalars, options, or newtypes or unit enum variants
+/// of those.
+pub fn bind_named<T: Serialize + ?Sized>(stmt: &mut PreparedStatement,
+                                         value: &T)
+                                         -> SqliteResult<()> {
+    value.serialize(ParamSerializer::new(stmt))
+}
+
+/// Like `bind_named()`, but skip fields with no such parameter, e.g.
+/// to bind a struct to a statement that uses only some of its fields.
+pub fn bind_named_lenient<T: Serialize + ?Sized>(stmt: &mut PreparedStatement,
+                                                 value: &T)
+                                                 -> SqliteResult<()> {
+    value.serialize(ParamSerializer::lenient(stmt))
+}
+
+/// Build a `T` from the columns of `row`.
+///
+/// Structs and maps are read by column name; tuples and sequences by
+/// position. Text and blobs may be borrowed from the row.
+pub fn from_row<'a, 'res, 'row, T>(row: &'a ResultRow<'res, 'row>) -> SqliteResult<T>
+    where T: de::Deserialize<'a>
+{
+    T::deserialize(RowDeserializer { row: row })
+}
+
+
+/// A serde `Serializer` that binds struct fields to named parameters;
+/// cf `bind_named()`.
+pub struct ParamSerializer<'s> {
+    stmt: &'s mut PreparedStatement,
+    lenient: bool,
+}
+
+impl<'s> ParamSerializer<'s> {
+    /// Bind to the parameters of `stmt`, failing on fields with no
+    /// such parameter.
+    pub fn new(stmt: &'s mut PreparedStatement) -> ParamSerializer<'s> {
+        ParamSerializer {
+            stmt: stmt,
+            lenient: false,
+        }
+    }
+
+    /// Bind to the parameters of `stmt`, skipping fields with no such
+    /// parameter.
+    pub fn lenient(stmt: &'s mut PreparedStatement) -> ParamSerializer<'s> {
+        ParamSerializer {
+            stmt: stmt,
+            lenient: true,
+        }
+    }
+}
+
+/// Generate `Serializer` methods that fail with `unsupported()`.
+macro_rules! reject {
+    ($($method:ident($($ty:ty),*) -> $ret:ty;)*) => {$(
+        fn $method(self, $(_: $ty),*) -> Result<$ret, SqliteError> {
+            Err(unsupported(stringify!($method)))
+        }
+    )*}
+}
+
+impl<'s> ser::Serializer for ParamSerializer<'s> {
+    type Ok = ();
+    type Error = SqliteError;
+    type SerializeSeq = Impossible<(), SqliteError>;
+    type SerializeTuple = Impossible<(), SqliteError>;
+    type SerializeTupleStruct = Impossible<(), SqliteError>;
+    type SerializeTupleVariant = Impossible<(), SqliteError>;
+    type SerializeMap = ParamFields<'s>;
+    type SerializeStruct = ParamFields<'s>;
+    type SerializeStructVariant = Impossible<(), SqliteError>;
+
+    fn serialize_map(self, _len: Option<usize>) -> SqliteResult<ParamFields<'s>> {
+        self.stmt.clear_bindings();
+        Ok(ParamFields {
+            stmt: self.stmt,
+            lenient: self.lenient,
+            key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> SqliteResult<ParamFields<'s>> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self,
+                                                       _name: &'static str,
+                                                       value: &T)
+                                                       -> SqliteResult<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> SqliteResult<()> {
+        value.serialize(self)
+    }
+
+    reject! {
+        serialize_bool(bool) -> ();
+        serialize_i8(i8) -> ();
+        serialize_i16(i16) -> ();
+        serialize_i32(i32) -> ();
+        serialize_i64(i64) -> ();
+        serialize_u8(u8) -> ();
+        serialize_u16(u16) -> ();
+        serialize_u32(u32) -> ();
+        serialize_u64(u64) -> ();
+        serialize_f32(f32) -> ();
+        serialize_f64(f64) -> ();
+        serialize_char(char) -> ();
+        serialize_str(&str) -> ();
+        serialize_bytes(&[u8]) -> ();
+        serialize_none() -> ();
+        serialize_unit() -> ();
+        serialize_unit_struct(&'static str) -> ();
+        serialize_unit_variant(&'static str, u32, &'static str) -> ();
+        serialize_seq(Option<usize>) -> Self::SerializeSeq;
+        serialize_tuple(usize) -> Self::SerializeTuple;
+        serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct;
+        serialize_tuple_variant(&'static str, u32, &'static str, usize)
+            -> Self::SerializeTupleVariant;
+        serialize_struct_variant(&'static str, u32, &'static str, usize)
+            -> Self::SerializeStructVariant;
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(self,
+                                                        _name: &'static str,
+                                                        _index: u32,
+                                                        _variant: &'static str,
+                                                        _value: &T)
+                                                        -> SqliteResult<()> {
+        Err(unsupported("serialize_newtype_variant"))
+    }
+}
+
+/// Fields of a struct or entries of a map being bound; cf
+/// `ParamSerializer`.
+pub struct ParamFields<'s> {
+    stmt: &'s mut PreparedStatement,
+    lenient: bool,
+    key: Option<String>,
+}
+
+impl<'s> ParamFields<'s> {
+    fn bind<T: Serialize + ?Sized>(&mut self, name: &str, value: &T) -> SqliteResult<()> {
+        match self.stmt.parameter_index(name) {
+            Some(ix) => try!(value.serialize(ValueSerializer)).to_sql(self.stmt, ix),
+            None if self.lenient => Ok(()),
+            None => {
+                Err(SqliteError {
+                    kind: SqliteErrorCode::SQLITE_RANGE,
+                    desc: "no such parameter name",
+                    detail: Some(name.to_string()),
+                })
+            }
+        }
+    }
+}
+
+impl<'s> ser::SerializeStruct for ParamFields<'s> {
+    type Ok = ();
+    type Error = SqliteError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self,
+                                              key: &'static str,
+                                              value: &T)
+                                              -> SqliteResult<()> {
+        self.bind(key, value)
+    }
+
+    fn end(self) -> SqliteResult<()> {
+        Ok(())
+    }
+}
+
+impl<'s> ser::SerializeMap for ParamFields<'s> {
+    type Ok = ();
+    type Error = SqliteError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> SqliteResult<()> {
+        match try!(key.serialize(ValueSerializer)) {
+            Value::Text(name) => {
+                self.key = Some(name);
+                Ok(())
+            }
+            other => Err(unsupported(&format!("map key {:?}", other))),
+        }
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> SqliteResult<()> {
+        let name = self.key.take().unwrap_or_default();
+        self.bind(&name, value)
+    }
+
+    fn end(self) -> SqliteResult<()> {
+        Ok(())
+    }
+}
+
+
+/// Serialize a scalar as a `Value`.
+struct ValueSerializer;
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = SqliteError;
+    type SerializeSeq = ByteSeq;
+    type SerializeTuple = Impossible<Value, SqliteError>;
+    type SerializeTupleStruct = Impossible<Value, SqliteError>;
+    type SerializeTupleVariant = Impossible<Value, SqliteError>;
+    type SerializeMap = Impossible<Value, SqliteError>;
+    type SerializeStruct = Impossible<Value, SqliteError>;
+    type SerializeStructVariant = Impossible<Value, SqliteError>;
+
+    fn serialize_bool(self, v: bool) -> SqliteResult<Value> {
+        Ok(Value::Integer(if v { 1 } else { 0 }))
+    }
+
+    fn serialize_i8(self, v: i8) -> SqliteResult<Value> {
+        Ok(Value::Integer(v as i64))
+    }
+
+    fn serialize_i16(self, v: i16) -> SqliteResult<Value> {
+        Ok(Value::Integer(v as i64))
+    }
+
+    fn serialize_i32(self, v: i32) -> SqliteResult<Value> {
+        Ok(Value::Integer(v as i64))
+    }
+
+    fn serialize_i64(self, v: i64) -> SqliteResult<Value> {
+        Ok(Value::Integer(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> SqliteResult<Value> {
+        Ok(Value::Integer(v as i64))
+    }
+
+    fn serialize_u16(self, v: u16) -> SqliteResult<Value> {
+        Ok(Value::Integer(v as i64))
+    }
+
+    fn serialize_u32(self, v: u32) -> SqliteResult<Value> {
+        Ok(Value::Integer(v as i64))
+    }
+
+    fn serialize_u64(self, v: u64) -> SqliteResult<Value> {
+        if v > i64::MAX as u64 {
+            return Err(unsupported(&format!("{} does not fit in an sqlite INTEGER (i64)", v)));
+        }
+        Ok(Value::Integer(v as i64))
+    }
+
+    fn serialize_f32(self, v: f32) -> SqliteResult<Value> {
+        Ok(Value::Real(v as f64))
+    }
+
+    fn serialize_f64(self, v: f64) -> SqliteResult<Value> {
+        Ok(Value::Real(v))
+    }
+
+    fn serialize_char(self, v: char) -> SqliteResult<Value> {
+        Ok(Value::Text(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> SqliteResult<Value> {
+        Ok(Value::Text(v.to_string()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> SqliteResult<Value> {
+        Ok(Value::Blob(v.to_vec()))
+    }
+
+    fn serialize_none(self) -> SqliteResult<Value> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> SqliteResult<Value> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> SqliteResult<Value> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> SqliteResult<Value> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_variant(self,
+                              _name: &'static str,
+                              _index: u32,
+                              variant: &'static str)
+                              -> SqliteResult<Value> {
+        Ok(Value::Text(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self,
+                                                       _name: &'static str,
+                                                       value: &T)
+                                                       -> SqliteResult<Value> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(self,
+                                                        _name: &'static str,
+                                                        _index: u32,
+                                                        _variant: &'static str,
+                                                        _value: &T)
+                                                        -> SqliteResult<Value> {
+        Err(unsupported("serialize_newtype_variant"))
+    }
+
+    /// Sequences of bytes, e.g. `Vec<u8>`, are blobs.
+    fn serialize_seq(self, len: Option<usize>) -> SqliteResult<ByteSeq> {
+        Ok(ByteSeq { bytes: Vec::with_capacity(len.unwrap_or(0)) })
+    }
+
+    reject! {
+        serialize_tuple(usize) -> Self::SerializeTuple;
+        serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct;
+        serialize_tuple_variant(&'static str, u32, &'static str, usize)
+            -> Self::SerializeTupleVariant;
+        serialize_map(Option<usize>) -> Self::SerializeMap;
+        serialize_struct(&'static str, usize) -> Self::SerializeStruct;
+        serialize_struct_variant(&'static str, u32, &'static str, usize)
+            -> Self::SerializeStructVariant;
+    }
+}
+
+/// A sequence of bytes being collected into a blob.
+struct ByteSeq {
+    bytes: Vec<u8>,
+}
+
+impl ser::SerializeSeq for ByteSeq {
+    type Ok = Value;
+    type Error = SqliteError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> SqliteResult<()> {
+        match try!(value.serialize(ValueSerializer)) {
+            Value::Integer(b) if (0..=255).contains(&b) => {
+                self.bytes.push(b as u8);
+                Ok(())
+            }
+            other => Err(unsupported(&format!("sequence element {:?}; only bytes are supported", other))),
+        }
+    }
+
+    fn end(self) -> SqliteResult<Value> {
+        Ok(Value::Blob(self.bytes))
+    }
+}
+
+
+/// A serde `Deserializer` over the columns of a row; cf `from_row()`.
+pub struct RowDeserializer<'a, 'res: 'a, 'row: 'a> {
+    row: &'a ResultRow<'res, 'row>,
+}
+
+impl<'a, 'res, 'row> RowDeserializer<'a, 'res, 'row> {
+    /// Read from the columns of `row`.
+    pub fn new(row: &'a ResultRow<'res, 'row>) -> RowDeserializer<'a, 'res, 'row> {
+        RowDeserializer { row: row }
+    }
+}
+
+impl<'de, 'res, 'row> de::Deserializer<'de> for RowDeserializer<'de, 'res, 'row> {
+    type Error = SqliteError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> SqliteResult<V::Value> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> SqliteResult<V::Value> {
+        visitor.visit_map(Columns {
+            row: self.row,
+            col: 0,
+        })
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(self,
+                                           _name: &'static str,
+                                           _fields: &'static [&'static str],
+                                           visitor: V)
+                                           -> SqliteResult<V::Value> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> SqliteResult<V::Value> {
+        visitor.visit_seq(Columns {
+            row: self.row,
+            col: 0,
+        })
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> SqliteResult<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self,
+                                                 _name: &'static str,
+                                                 _len: usize,
+                                                 visitor: V)
+                                                 -> SqliteResult<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self,
+                                                   _name: &'static str,
+                                                   visitor: V)
+                                                   -> SqliteResult<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct enum identifier ignored_any
+    }
+}
+
+/// Columns of a row, as map entries by name or as a sequence.
+struct Columns<'a, 'res: 'a, 'row: 'a> {
+    row: &'a ResultRow<'res, 'row>,
+    col: ColIx,
+}
+
+impl<'de, 'res, 'row> MapAccess<'de> for Columns<'de, 'res, 'row> {
+    type Error = SqliteError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> SqliteResult<Option<K::Value>> {
+        if self.col >= self.row.column_count() {
+            return Ok(None);
+        }
+        let name = self.row.with_column_name(self.col, String::new(), |name| name.to_string());
+        seed.deserialize(name.into_deserializer()).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> SqliteResult<V::Value> {
+        let value = self.row.column_value(self.col);
+        self.col += 1;
+        seed.deserialize(ValueDeserializer { value: value })
+    }
+}
+
+impl<'de, 'res, 'row> SeqAccess<'de> for Columns<'de, 'res, 'row> {
+    type Error = SqliteError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self,
+                                                  seed: T)
+                                                  -> SqliteResult<Option<T::Value>> {
+        if self.col >= self.row.column_count() {
+            return Ok(None);
+        }
+        let value = self.row.column_value(self.col);
+        self.col += 1;
+        seed.deserialize(ValueDeserializer { value: value }).map(Some)
+    }
+}
+
+/// Deserialize a single column value.
+struct ValueDeserializer<'a> {
+    value: ValueRef<'a>,
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
+    type Error = SqliteError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> SqliteResult<V::Value> {
+        match self.value {
+            ValueRef::Null => visitor.visit_unit(),
+            ValueRef::Integer(i) => visitor.visit_i64(i),
+            ValueRef::Real(x) => visitor.visit_f64(x),
+            ValueRef::Text(s) => visitor.visit_borrowed_str(s),
+            ValueRef::Blob(b) => visitor.visit_borrowed_bytes(b),
+        }
+    }
+
+    /// Integers are booleans, as with `FromSql for bool`.
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> SqliteResult<V::Value> {
+        match self.value {
+            ValueRef::Integer(i) => visitor.visit_bool(i != 0),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> SqliteResult<V::Value> {
+        match self.value {
+            ValueRef::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    /// Blobs are sequences of bytes, e.g. for `Vec<u8>`.
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> SqliteResult<V::Value> {
+        match self.value {
+            ValueRef::Blob(b) => visitor.visit_seq(de::value::SeqDeserializer::new(b.iter().cloned())),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    /// Text names a unit variant.
+    fn deserialize_enum<V: Visitor<'de>>(self,
+                                         _name: &'static str,
+                                         _variants: &'static [&'static str],
+                                         visitor: V)
+                                         -> SqliteResult<V::Value> {
+        match self.value {
+            ValueRef::Text(s) => visitor.visit_enum(s.into_deserializer()),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self,
+                                                   _name: &'static str,
+                                                   visitor: V)
+                                                   -> SqliteResult<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+// Local Variables:
+// flycheck-rust-crate-root: "lib.rs"
+// End:
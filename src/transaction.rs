@@ -0,0 +1,608 @@
+//! Transactions as RAII guards.
+//!
+//! Rather than writing `BEGIN` and `COMMIT` by hand, use
+//! `DatabaseConnection::transaction()` to get a `Transaction` that
+//! rolls back when it goes out of scope unless it is committed.
+//! A panic or an early `try!()` return thus can't leave the
+//! connection in the middle of a transaction.
+//!
+//! ```rust
+//! extern crate sqlite3;
+//!
+//! use sqlite3::{DatabaseConnection, SqliteResult, StatementUpdate};
+//! use sqlite3::transaction::TransactionBehavior::Immediate;
+//!
+//! fn transfer(conn: &mut DatabaseConnection, amount: i64) -> SqliteResult<()> {
+//!     let tx = try!(conn.transaction(Immediate));
+//!     {
+//!         let mut debit = try!(tx.prepare(
+//!             "update account set balance = balance - $1 where id = 1"));
+//!         try!(debit.update(&[&amount]));
+//!         let mut credit = try!(tx.prepare(
+//!             "update account set balance = balance + $1 where id = 2"));
+//!         try!(credit.update(&[&amount]));
+//!     }
+//!     tx.commit()
+//! }
+//!
+//! pub fn main() {
+//!     let mut conn = DatabaseConnection::in_memory().unwrap();
+//!     conn.exec("create table account (id integer, balance integer);
+//!                insert into account values (1, 100), (2, 0)").unwrap();
+//!     transfer(&mut conn, 10).unwrap();
+//!     assert!(conn.get_autocommit());
+//! }
+//! ```
+//!
+//! Savepoints nest inside a connection, a transaction, or another
+//! savepoint. Committing a `Savepoint` does a `RELEASE`; dropping it
+//! unfinished does `ROLLBACK TO` followed by `RELEASE`, leaving any
+//! enclosing transaction open.
+//!
+//! `DatabaseConnection::with_retry()` runs a unit of work in a
+//! transaction, starting over when it runs into lock contention
+//! (`SQLITE_BUSY` or `SQLITE_LOCKED`) that `busy_timeout()` can't
+//! resolve, such as a deferred transaction upgrading to a writer.
+
+use std::cmp;
+use std::default::Default;
+use std::ops::{Deref, DerefMut};
+use std::thread;
+use time::{self, Duration};
+
+use super::{SqliteError, SqliteErrorCode, SqliteResult};
+use core::DatabaseConnection;
+
+/// How the database is locked when a transaction begins.
+///
+/// cf [BEGIN TRANSACTION][begin]
+///
+/// [begin]: http://www.sqlite.org/lang_transaction.html
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum TransactionBehavior {
+    /// Acquire locks lazily, on first read or write.
+    Deferred,
+    /// Acquire a write lock right away.
+    Immediate,
+    /// Acquire an exclusive lock right away.
+    Exclusive,
+}
+
+impl TransactionBehavior {
+    fn begin_sql(&self) -> &'static str {
+        match *self {
+            TransactionBehavior::Deferred => "BEGIN DEFERRED",
+            TransactionBehavior::Immediate => "BEGIN IMMEDIATE",
+            TransactionBehavior::Exclusive => "BEGIN EXCLUSIVE",
+        }
+    }
+}
+
+/// What to do with a transaction that is dropped without an explicit
+/// `commit()` or `rollback()`.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum DropBehavior {
+    /// Roll back the transaction (the default).
+    Rollback,
+    /// Commit the transaction.
+    Commit,
+}
+
+/// An open transaction on a `DatabaseConnection`.
+///
+/// The transaction dereferences to its connection, so statements can
+/// be prepared and executed as usual while it is alive.
+pub struct Transaction<'conn> {
+    conn: &'conn mut DatabaseConnection,
+    drop_behavior: DropBehavior,
+    finished: bool,
+}
+
+impl DatabaseConnection {
+    /// Begin a transaction, returning a guard that rolls it back on
+    /// drop unless it is committed.
+    pub fn transaction(&mut self, behavior: TransactionBehavior) -> SqliteResult<Transaction<'_>> {
+        try!(self.exec(behavior.begin_sql()));
+        Ok(Transaction {
+            conn: self,
+            drop_behavior: DropBehavior::Rollback,
+            finished: false,
+        })
+    }
+}
+
+impl<'conn> Transaction<'conn> {
+    /// What happens if the transaction is dropped unfinished.
+    pub fn drop_behavior(&self) -> DropBehavior {
+        self.drop_behavior
+    }
+
+    /// Choose whether to commit or roll back if the transaction is
+    /// dropped unfinished.
+    pub fn set_drop_behavior(&mut self, drop_behavior: DropBehavior) {
+        self.drop_behavior = drop_behavior;
+    }
+
+    /// Test whether the connection is still inside a transaction.
+    ///
+    /// sqlite rolls back automatically on some errors (e.g.
+    /// `SQLITE_FULL`), in which case this returns `false`.
+    ///
+    /// cf `sqlite3_get_autocommit`
+    pub fn is_active(&self) -> bool {
+        !self.conn.get_autocommit()
+    }
+
+    /// Commit the transaction.
+    ///
+    /// If the `COMMIT` fails (e.g. with `SQLITE_BUSY`), the
+    /// transaction is rolled back before the error is returned.
+    pub fn commit(mut self) -> SqliteResult<()> {
+        self.finished = true;
+        commit_or_rollback(self.conn)
+    }
+
+    /// Roll back the transaction.
+    pub fn rollback(mut self) -> SqliteResult<()> {
+        self.finished = true;
+        rollback(self.conn)
+    }
+
+    /// Begin a savepoint nested in this transaction.
+    pub fn savepoint(&mut self, name: &str) -> SqliteResult<Savepoint<'_>> {
+        Savepoint::begin(self.conn, name)
+    }
+}
+
+fn commit_or_rollback(conn: &mut DatabaseConnection) -> SqliteResult<()> {
+    match conn.exec("COMMIT") {
+        Ok(()) => Ok(()),
+        Err(err) => {
+            // ignore any error from rollback; report the original one.
+            let _ = rollback(conn);
+            Err(err)
+        }
+    }
+}
+
+fn rollback(conn: &mut DatabaseConnection) -> SqliteResult<()> {
+    if conn.get_autocommit() {
+        // already rolled back by sqlite
+        Ok(())
+    } else {
+        conn.exec("ROLLBACK")
+    }
+}
+
+impl<'conn> Deref for Transaction<'conn> {
+    type Target = DatabaseConnection;
+
+    fn deref(&self) -> &DatabaseConnection {
+        self.conn
+    }
+}
+
+impl<'conn> DerefMut for Transaction<'conn> {
+    fn deref_mut(&mut self) -> &mut DatabaseConnection {
+        self.conn
+    }
+}
+
+impl<'conn> Drop for Transaction<'conn> {
+    /// Finish the transaction according to its `drop_behavior`.
+    ///
+    /// Errors are ignored; use `commit()` or `rollback()` to see them.
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+        let _ = match self.drop_behavior {
+            DropBehavior::Rollback => rollback(self.conn),
+            DropBehavior::Commit => commit_or_rollback(self.conn),
+        };
+    }
+}
+
+
+/// When and how often `with_retry()` starts a unit of work over.
+#[derive(Debug, Copy, Clone)]
+pub struct RetryPolicy {
+    /// How the transaction for each attempt begins.
+    pub behavior: TransactionBehavior,
+    /// Total number of attempts, including the first.
+    pub max_attempts: u32,
+    /// Delay before the second attempt; it doubles for each attempt
+    /// after that.
+    pub initial_backoff: Duration,
+    /// Upper bound on the delay between attempts.
+    pub max_backoff: Duration,
+    /// Whether to pick each delay at random from between half and all
+    /// of the computed backoff, so that competing writers spread out.
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            behavior: TransactionBehavior::Deferred,
+            max_attempts: 5,
+            initial_backoff: Duration::milliseconds(10),
+            max_backoff: Duration::seconds(1),
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay to wait after `attempt` (counting from 1) failed.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exp = cmp::min(attempt - 1, 30);
+        let max_ms = cmp::max(self.max_backoff.num_milliseconds(), 0);
+        let ms = cmp::min(self.initial_backoff.num_milliseconds().saturating_mul(1 << exp),
+                          max_ms);
+        if self.jitter && ms > 1 {
+            // A cheap xorshift of the clock is random enough to
+            // de-synchronize competing connections.
+            let mut x = time::precise_time_ns() | 1;
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            let half = ms / 2;
+            Duration::milliseconds(half + (x % (ms - half + 1) as u64) as i64)
+        } else {
+            Duration::milliseconds(ms)
+        }
+    }
+}
+
+fn is_contention(err: &SqliteError) -> bool {
+    err.kind == SqliteErrorCode::SQLITE_BUSY || err.kind == SqliteErrorCode::SQLITE_LOCKED
+}
+
+impl DatabaseConnection {
+    /// Run `work` in a transaction and commit it, starting over on
+    /// `SQLITE_BUSY` or `SQLITE_LOCKED`.
+    ///
+    /// Each failed attempt is rolled back before waiting and trying
+    /// again, so `work` must be safe to re-run. Other errors are
+    /// returned right away. When the attempts run out, the last error
+    /// is returned with the attempt count noted in its `detail`.
+    pub fn with_retry<T, F>(&mut self, policy: &RetryPolicy, mut work: F) -> SqliteResult<T>
+        where F: FnMut(&mut Transaction) -> SqliteResult<T>
+    {
+        let mut attempt = 1;
+        loop {
+            let outcome = match self.transaction(policy.behavior) {
+                Ok(mut tx) => work(&mut tx).and_then(|value| tx.commit().map(|()| value)),
+                Err(err) => Err(err),
+            };
+            match outcome {
+                Ok(value) => return Ok(value),
+                Err(err) => {
+                    if !is_contention(&err) {
+                        return Err(err);
+                    }
+                    if attempt >= policy.max_attempts {
+                        let detail = match err.detail {
+                            Some(msg) => format!("{} (after {} attempts)", msg, attempt),
+                            None => format!("after {} attempts", attempt),
+                        };
+                        return Err(SqliteError { detail: Some(detail), ..err });
+                    }
+                }
+            }
+            if let Ok(delay) = policy.backoff(attempt).to_std() {
+                thread::sleep(delay);
+            }
+            attempt += 1;
+        }
+    }
+}
+
+
+/// A savepoint: a named, nestable unit of work.
+///
+/// Like `Transaction`, it dereferences to its connection.
+pub struct Savepoint<'conn> {
+    conn: &'conn mut DatabaseConnection,
+    name: String,
+    depth: u32,
+    finished: bool,
+}
+
+impl DatabaseConnection {
+    /// Begin a savepoint, starting a transaction if none is open.
+    ///
+    /// Nested in another savepoint (even via `Deref`), it is named
+    /// for its depth; cf `Savepoint::name()`.
+    pub fn savepoint(&mut self, name: &str) -> SqliteResult<Savepoint<'_>> {
+        Savepoint::begin(self, name)
+    }
+}
+
+impl<'conn> Savepoint<'conn> {
+    fn begin(conn: &'conn mut DatabaseConnection, name: &str) -> SqliteResult<Savepoint<'conn>> {
+        // Qualify the name by nesting depth, tracked on the connection,
+        // so that nested savepoints with the same name never refer to
+        // each other.
+        let depth = conn.savepoint_depth + 1;
+        let name = format!("{}_{}", name, depth);
+        try!(savepoint_exec(conn, "SAVEPOINT", &name));
+        conn.savepoint_depth = depth;
+        Ok(Savepoint {
+            conn: conn,
+            name: name,
+            depth: depth,
+            finished: false,
+        })
+    }
+
+    /// Name of the savepoint, as given to sqlite.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Nesting depth: 1 for a savepoint directly on a connection or
+    /// transaction, 2 for one nested in that, and so on.
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    /// Begin a savepoint nested in this one.
+    pub fn savepoint(&mut self, name: &str) -> SqliteResult<Savepoint<'_>> {
+        Savepoint::begin(self.conn, name)
+    }
+
+    /// Commit (`RELEASE`) the savepoint.
+    pub fn commit(mut self) -> SqliteResult<()> {
+        self.finish();
+        savepoint_exec(self.conn, "RELEASE", &self.name)
+    }
+
+    /// Undo the work done since the savepoint began, and release it.
+    pub fn rollback(mut self) -> SqliteResult<()> {
+        self.finish();
+        savepoint_rollback(self.conn, &self.name)
+    }
+
+    fn finish(&mut self) {
+        self.finished = true;
+        self.conn.savepoint_depth = self.depth - 1;
+    }
+}
+
+fn savepoint_rollback(conn: &mut DatabaseConnection, name: &str) -> SqliteResult<()> {
+    try!(savepoint_exec(conn, "ROLLBACK TO", name));
+    savepoint_exec(conn, "RELEASE", name)
+}
+
+/// Execute `verb` on savepoint `name`, noting the savepoint in any
+/// error detail.
+fn savepoint_exec(conn: &mut DatabaseConnection, verb: &str, name: &str) -> SqliteResult<()> {
+    let sql = format!("{} \"{}\"", verb, name.replace("\"", "\"\""));
+    conn.exec(&sql).map_err(|err| {
+        let detail = match err.detail {
+            Some(msg) => format!("savepoint {}: {}", name, msg),
+            None => format!("savepoint {}", name),
+        };
+        SqliteError { detail: Some(detail), ..err }
+    })
+}
+
+impl<'conn> Deref for Savepoint<'conn> {
+    type Target = DatabaseConnection;
+
+    fn deref(&self) -> &DatabaseConnection {
+        self.conn
+    }
+}
+
+impl<'conn> DerefMut for Savepoint<'conn> {
+    fn deref_mut(&mut self) -> &mut DatabaseConnection {
+        self.conn
+    }
+}
+
+impl<'conn> Drop for Savepoint<'conn> {
+    /// Roll back an unfinished savepoint, ignoring errors.
+    fn drop(&mut self) {
+        if !self.finished {
+            self.finish();
+            let _ = savepoint_rollback(self.conn, &self.name);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::TransactionBehavior::{Deferred, Immediate, Exclusive};
+    use super::{DropBehavior, RetryPolicy};
+    use access;
+    use core::DatabaseConnection;
+    use super::super::{SqliteErrorCode, SqliteResult};
+    use super::super::{StatementUpdate, ResultRowAccess};
+    use std::default::Default;
+    use std::env::temp_dir;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process;
+    use time::Duration;
+
+    /// A database file, unique to this process, removed on drop.
+    struct TempDb(PathBuf);
+
+    impl TempDb {
+        fn new(name: &str) -> TempDb {
+            let mut path = temp_dir();
+            path.push(format!("{}-{}.db", name, process::id()));
+            let _ = fs::remove_file(&path);
+            TempDb(path)
+        }
+
+        fn filename(&self) -> &str {
+            self.0.to_str().unwrap()
+        }
+    }
+
+    impl Drop for TempDb {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    fn count(conn: &DatabaseConnection) -> i32 {
+        let mut stmt = conn.prepare("select count(*) from t").unwrap();
+        let mut results = stmt.execute();
+        let row = results.step().unwrap().unwrap();
+        row.get(0u32)
+    }
+
+    #[test]
+    fn commit_persists() {
+        let mut conn = DatabaseConnection::in_memory().unwrap();
+        conn.exec("create table t (x integer)").unwrap();
+        {
+            let tx = conn.transaction(Deferred).unwrap();
+            assert!(tx.is_active());
+            {
+                let mut stmt = tx.prepare("insert into t values ($1)").unwrap();
+                assert_eq!(stmt.update(&[&1]), Ok(1));
+            }
+            tx.commit().unwrap();
+        }
+        assert!(conn.get_autocommit());
+        assert_eq!(count(&conn), 1);
+    }
+
+    #[test]
+    fn drop_rolls_back() {
+        fn go(conn: &mut DatabaseConnection) -> SqliteResult<()> {
+            let mut tx = try!(conn.transaction(Immediate));
+            try!(tx.exec("insert into t values (1)"));
+            try!(tx.exec("insert into bogus values (1)"));
+            tx.commit()
+        }
+        let mut conn = DatabaseConnection::in_memory().unwrap();
+        conn.exec("create table t (x integer)").unwrap();
+        assert!(go(&mut conn).is_err());
+        assert!(conn.get_autocommit());
+        assert_eq!(count(&conn), 0);
+    }
+
+    #[test]
+    fn commit_on_drop() {
+        let mut conn = DatabaseConnection::in_memory().unwrap();
+        conn.exec("create table t (x integer)").unwrap();
+        {
+            let mut tx = conn.transaction(Exclusive).unwrap();
+            tx.set_drop_behavior(DropBehavior::Commit);
+            tx.exec("insert into t values (1)").unwrap();
+        }
+        assert_eq!(count(&conn), 1);
+    }
+
+    #[test]
+    fn explicit_rollback() {
+        let mut conn = DatabaseConnection::in_memory().unwrap();
+        conn.exec("create table t (x integer)").unwrap();
+        let mut tx = conn.transaction(Deferred).unwrap();
+        tx.exec("insert into t values (1)").unwrap();
+        assert_eq!(count(&tx), 1);
+        tx.rollback().unwrap();
+        assert_eq!(count(&conn), 0);
+    }
+
+    #[test]
+    fn retry_on_busy() {
+        // declared first, so the file is removed after the connections close
+        let db_file = TempDb::new("retry_on_busy");
+        let filename = db_file.filename();
+
+        let mut writer = access::open(filename, None).unwrap();
+        writer.exec("create table t (x integer)").unwrap();
+        let mut other = access::open(filename, None).unwrap();
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::milliseconds(1),
+            ..Default::default()
+        };
+
+        {
+            let _lock = other.transaction(Exclusive).unwrap();
+            let mut attempts = 0;
+            let err = writer.with_retry(&policy, |tx| {
+                    attempts += 1;
+                    tx.exec("insert into t values (1)")
+                })
+                .err()
+                .unwrap();
+            assert_eq!(attempts, 3);
+            assert_eq!(err.kind, SqliteErrorCode::SQLITE_BUSY);
+            assert!(err.detail().unwrap().ends_with("(after 3 attempts)"));
+        }
+
+        // release the lock after the first attempt fails
+        let mut lock = Some(other.transaction(Exclusive).unwrap());
+        let mut attempts = 0;
+        writer.with_retry(&policy, |tx| {
+                attempts += 1;
+                let result = tx.exec("insert into t values (1)");
+                if let Some(l) = lock.take() {
+                    l.commit().unwrap();
+                }
+                result
+            })
+            .unwrap();
+        assert_eq!(attempts, 2);
+        assert_eq!(count(&writer), 1);
+    }
+
+    #[test]
+    fn nested_savepoints() {
+        let mut conn = DatabaseConnection::in_memory().unwrap();
+        conn.exec("create table t (x integer)").unwrap();
+        {
+            let mut tx = conn.transaction(Deferred).unwrap();
+            {
+                let mut outer = tx.savepoint("sp").unwrap();
+                outer.exec("insert into t values (1)").unwrap();
+                {
+                    let mut inner = outer.savepoint("sp").unwrap();
+                    assert_eq!(inner.depth(), 2);
+                    assert!(inner.name() != "sp_1");
+                    inner.exec("insert into t values (2)").unwrap();
+                    // dropped: rolled back
+                }
+                {
+                    // via Deref, too
+                    let mut inner = (*outer).savepoint("sp").unwrap();
+                    assert_eq!((inner.name(), inner.depth()), ("sp_2", 2));
+                    inner.exec("insert into t values (3)").unwrap();
+                    inner.rollback().unwrap();
+                }
+                assert_eq!(count(&outer), 1);
+                assert_eq!(outer.name(), "sp_1");
+                outer.commit().unwrap();
+            }
+            assert!(tx.is_active());
+            tx.commit().unwrap();
+        }
+        assert_eq!(count(&conn), 1);
+    }
+
+    #[test]
+    fn savepoint_error_detail() {
+        let mut conn = DatabaseConnection::in_memory().unwrap();
+        conn.exec("create table t (x integer)").unwrap();
+        let mut sp = conn.savepoint("import").unwrap();
+        sp.exec("ROLLBACK").unwrap();
+        let err = sp.commit().err().unwrap();
+        assert_eq!(err.detail(),
+                   Some("savepoint import_1: no such savepoint: import_1".to_string()));
+    }
+}
+
+// Local Variables:
+// flycheck-rust-crate-root: "lib.rs"
+// End:
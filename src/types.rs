@@ -2,8 +2,16 @@
 
 use super::{PreparedStatement, ResultRow, ColIx, ParamIx};
 use super::{SqliteResult, SqliteErrorCode, SqliteError};
-use super::ColumnType::SQLITE_NULL;
+use super::ColumnType;
+use super::ColumnType::{SQLITE_BLOB, SQLITE_FLOAT, SQLITE_INTEGER, SQLITE_NULL, SQLITE_TEXT};
 
+use std::borrow::{Cow, ToOwned};
+use std::convert::TryFrom;
+use std::f32;
+use std::rc::Rc;
+use std::str;
+use std::sync::Arc;
+use std::time::Duration as StdDuration;
 use time;
 
 /// Values that can be bound to parameters in prepared statements.
@@ -21,23 +29,161 @@ pub trait ToSql {
 /// [column]: http://www.sqlite.org/c3ref/column_blob.html
 ///
 ///   - *TODO: many more implementors, including Option<T>*
+///
+/// Unless the statement is in strict mode (cf
+/// `PreparedStatement::set_strict()`), implementations rely on sqlite's
+/// conversions between storage classes; e.g. `NULL` becomes `0` or `""`.
 pub trait FromSql: Sized {
     /// Try to extract a `Self` type value from the `col`th colum of a `ResultRow`.
     fn from_sql(row: &ResultRow, col: ColIx) -> SqliteResult<Self>;
 }
 
+/// Result values that may borrow from the row they come from.
+///
+/// Any `FromSql` type is also `FromSqlRef`; in addition, `&str`,
+/// `&[u8]` and `ValueRef` refer to sqlite's copy of the value, which
+/// lasts until the `ResultSet` steps to the next row.
+pub trait FromSqlRef<'a>: Sized {
+    /// Try to extract a `Self` type value from the `col`th colum of a `ResultRow`.
+    fn from_sql_ref(row: &'a ResultRow, col: ColIx) -> SqliteResult<Self>;
+}
+
+impl<'a, T: FromSql> FromSqlRef<'a> for T {
+    fn from_sql_ref(row: &'a ResultRow, col: ColIx) -> SqliteResult<T> {
+        T::from_sql(row, col)
+    }
+}
+
+fn text_ref<'a>(row: &'a ResultRow, col: ColIx, ty: &str) -> SqliteResult<&'a str> {
+    try!(check_column_type(row, col, ty, &[SQLITE_TEXT]));
+    let bytes = row.column_slice(col).unwrap_or(&[]);
+    str::from_utf8(bytes).map_err(|err| {
+        SqliteError {
+            kind: SqliteErrorCode::SQLITE_MISMATCH,
+            desc: "text is not valid UTF-8",
+            detail: Some(format!("column {}: {}", col, err)),
+        }
+    })
+}
+
+impl<'a> FromSqlRef<'a> for &'a str {
+    /// Text that is not valid UTF-8 is an error.
+    fn from_sql_ref(row: &'a ResultRow, col: ColIx) -> SqliteResult<&'a str> {
+        text_ref(row, col, "&str")
+    }
+}
+
+impl<'a> FromSqlRef<'a> for &'a [u8] {
+    fn from_sql_ref(row: &'a ResultRow, col: ColIx) -> SqliteResult<&'a [u8]> {
+        try!(check_column_type(row, col, "&[u8]", &[SQLITE_BLOB, SQLITE_TEXT]));
+        Ok(row.column_slice(col).unwrap_or(&[]))
+    }
+}
+
+impl<'a> FromSqlRef<'a> for Option<&'a str> {
+    fn from_sql_ref(row: &'a ResultRow, col: ColIx) -> SqliteResult<Option<&'a str>> {
+        match row.column_type(col) {
+            SQLITE_NULL => Ok(None),
+            _ => FromSqlRef::from_sql_ref(row, col).map(Some),
+        }
+    }
+}
+
+impl<'a> FromSqlRef<'a> for Option<&'a [u8]> {
+    fn from_sql_ref(row: &'a ResultRow, col: ColIx) -> SqliteResult<Option<&'a [u8]>> {
+        match row.column_type(col) {
+            SQLITE_NULL => Ok(None),
+            _ => FromSqlRef::from_sql_ref(row, col).map(Some),
+        }
+    }
+}
+
+/// In strict mode, check that the storage class of a column is one
+/// of `expected`, which `ty` can be converted from without loss.
+pub fn check_column_type(row: &ResultRow,
+                         col: ColIx,
+                         ty: &str,
+                         expected: &[ColumnType])
+                         -> SqliteResult<()> {
+    if !row.is_strict() {
+        return Ok(());
+    }
+    let actual = row.column_type(col);
+    if expected.contains(&actual) {
+        return Ok(());
+    }
+    let name = row.with_column_name(col, String::new(), |name| format!(" ({})", name));
+    Err(SqliteError {
+        kind: SqliteErrorCode::SQLITE_MISMATCH,
+        desc: "column type mismatch",
+        detail: Some(format!("column {}{}: can't get {} from {:?}", col, name, ty, actual)),
+    })
+}
+
 impl ToSql for i32 {
     fn to_sql(&self, s: &mut PreparedStatement, ix: ParamIx) -> SqliteResult<()> {
         s.bind_int(ix, *self)
     }
 }
 
-impl FromSql for i32 {
-    fn from_sql(row: &ResultRow, col: ColIx) -> SqliteResult<i32> {
-        Ok(row.column_int(col))
+fn out_of_range<T: ::std::fmt::Display>(value: T, ty: &str, col: Option<ColIx>) -> SqliteError {
+    let detail = match col {
+        Some(col) => format!("column {}: {} does not fit in {}", col, value, ty),
+        None => format!("{} does not fit in {}", value, ty),
+    };
+    SqliteError {
+        kind: SqliteErrorCode::SQLITE_MISMATCH,
+        desc: "integer out of range",
+        detail: Some(detail),
     }
 }
 
+/// Get integers via `column_int64`, failing with `SQLITE_MISMATCH`
+/// rather than truncating.
+macro_rules! from_sql_checked {
+    ($($t:ty),*) => {$(
+        impl FromSql for $t {
+            fn from_sql(row: &ResultRow, col: ColIx) -> SqliteResult<$t> {
+                try!(check_column_type(row, col, stringify!($t), &[SQLITE_INTEGER]));
+                let value = row.column_int64(col);
+                <$t>::try_from(value).map_err(|_| out_of_range(value, stringify!($t), Some(col)))
+            }
+        }
+    )*}
+}
+
+from_sql_checked!(i8, i16, i32, u8, u16, u32, u64, usize, isize);
+
+/// Bind integers that always fit in an `i64`.
+macro_rules! to_sql_widened {
+    ($($t:ty),*) => {$(
+        impl ToSql for $t {
+            fn to_sql(&self, s: &mut PreparedStatement, ix: ParamIx) -> SqliteResult<()> {
+                s.bind_int64(ix, *self as i64)
+            }
+        }
+    )*}
+}
+
+to_sql_widened!(i8, i16, u8, u16, u32);
+
+/// Bind integers that may not fit in an `i64`, failing with
+/// `SQLITE_MISMATCH` for those that don't.
+macro_rules! to_sql_checked {
+    ($($t:ty),*) => {$(
+        impl ToSql for $t {
+            fn to_sql(&self, s: &mut PreparedStatement, ix: ParamIx) -> SqliteResult<()> {
+                match i64::try_from(*self) {
+                    Ok(value) => s.bind_int64(ix, value),
+                    Err(_) => Err(out_of_range(*self, "an sqlite INTEGER (i64)", None)),
+                }
+            }
+        }
+    )*}
+}
+
+to_sql_checked!(u64, usize, isize);
+
 impl ToSql for i64 {
     fn to_sql(&self, s: &mut PreparedStatement, ix: ParamIx) -> SqliteResult<()> {
         s.bind_int64(ix, *self)
@@ -46,6 +192,7 @@ impl ToSql for i64 {
 
 impl FromSql for i64 {
     fn from_sql(row: &ResultRow, col: ColIx) -> SqliteResult<i64> {
+        try!(check_column_type(row, col, "i64", &[SQLITE_INTEGER]));
         Ok(row.column_int64(col))
     }
 }
@@ -56,12 +203,58 @@ impl ToSql for f64 {
     }
 }
 
+/// In strict mode, check that an INTEGER column converts to the
+/// floating point type `ty` exactly, i.e. that `exact(value)` holds.
+fn check_exact_float(row: &ResultRow,
+                     col: ColIx,
+                     ty: &str,
+                     exact: fn(i64) -> bool)
+                     -> SqliteResult<()> {
+    if !row.is_strict() || row.column_type(col) != SQLITE_INTEGER {
+        return Ok(());
+    }
+    let value = row.column_int64(col);
+    if exact(value) {
+        return Ok(());
+    }
+    Err(SqliteError {
+        kind: SqliteErrorCode::SQLITE_MISMATCH,
+        desc: "integer not exactly representable",
+        detail: Some(format!("column {}: {} is not exact in {}", col, value, ty)),
+    })
+}
+
 impl FromSql for f64 {
+    /// In strict mode, fail with `SQLITE_MISMATCH` for integers that
+    /// don't convert exactly, e.g. beyond 2^53.
     fn from_sql(row: &ResultRow, col: ColIx) -> SqliteResult<f64> {
+        try!(check_column_type(row, col, "f64", &[SQLITE_FLOAT, SQLITE_INTEGER]));
+        try!(check_exact_float(row, col, "f64", |n| n as f64 as i128 == n as i128));
         Ok(row.column_double(col))
     }
 }
 
+impl ToSql for f32 {
+    fn to_sql(&self, s: &mut PreparedStatement, ix: ParamIx) -> SqliteResult<()> {
+        s.bind_double(ix, *self as f64)
+    }
+}
+
+impl FromSql for f32 {
+    /// Fail with `SQLITE_MISMATCH` for finite values beyond the range
+    /// of `f32`; precision is lost silently, except that in strict mode
+    /// integers that don't convert exactly (e.g. beyond 2^24) fail.
+    fn from_sql(row: &ResultRow, col: ColIx) -> SqliteResult<f32> {
+        try!(check_column_type(row, col, "f32", &[SQLITE_FLOAT, SQLITE_INTEGER]));
+        try!(check_exact_float(row, col, "f32", |n| n as f32 as i128 == n as i128));
+        let value = row.column_double(col);
+        if value.is_finite() && value.abs() > f32::MAX as f64 {
+            return Err(out_of_range(value, "f32", Some(col)));
+        }
+        Ok(value as f32)
+    }
+}
+
 impl ToSql for bool {
     fn to_sql(&self, s: &mut PreparedStatement, ix: ParamIx) -> SqliteResult<()> {
         s.bind_int(ix, if *self { 1 } else { 0 })
@@ -70,20 +263,21 @@ impl ToSql for bool {
 
 impl FromSql for bool {
     fn from_sql(row: &ResultRow, col: ColIx) -> SqliteResult<bool> {
-        Ok(row.column_int(col) != 0)
+        try!(check_column_type(row, col, "bool", &[SQLITE_INTEGER]));
+        Ok(row.column_int64(col) != 0)
     }
 }
 
-impl<T: ToSql + Clone> ToSql for Option<T> {
+impl<T: ToSql> ToSql for Option<T> {
     fn to_sql(&self, s: &mut PreparedStatement, ix: ParamIx) -> SqliteResult<()> {
-        match (*self).clone() {
-            Some(x) => x.to_sql(s, ix),
+        match *self {
+            Some(ref x) => x.to_sql(s, ix),
             None => s.bind_null(ix),
         }
     }
 }
 
-impl<T: FromSql + Clone> FromSql for Option<T> {
+impl<T: FromSql> FromSql for Option<T> {
     fn from_sql(row: &ResultRow, col: ColIx) -> SqliteResult<Option<T>> {
         match row.column_type(col) {
             SQLITE_NULL => Ok(None),
@@ -92,6 +286,42 @@ impl<T: FromSql + Clone> FromSql for Option<T> {
     }
 }
 
+impl<'a, T: ToSql + ?Sized> ToSql for &'a T {
+    fn to_sql(&self, s: &mut PreparedStatement, ix: ParamIx) -> SqliteResult<()> {
+        (**self).to_sql(s, ix)
+    }
+}
+
+impl<T: ToSql + ?Sized> ToSql for Box<T> {
+    fn to_sql(&self, s: &mut PreparedStatement, ix: ParamIx) -> SqliteResult<()> {
+        (**self).to_sql(s, ix)
+    }
+}
+
+impl<T: ToSql + ?Sized> ToSql for Rc<T> {
+    fn to_sql(&self, s: &mut PreparedStatement, ix: ParamIx) -> SqliteResult<()> {
+        (**self).to_sql(s, ix)
+    }
+}
+
+impl<T: ToSql + ?Sized> ToSql for Arc<T> {
+    fn to_sql(&self, s: &mut PreparedStatement, ix: ParamIx) -> SqliteResult<()> {
+        (**self).to_sql(s, ix)
+    }
+}
+
+impl<'a, T: ToSql + ToOwned + ?Sized> ToSql for Cow<'a, T> {
+    fn to_sql(&self, s: &mut PreparedStatement, ix: ParamIx) -> SqliteResult<()> {
+        (**self).to_sql(s, ix)
+    }
+}
+
+impl ToSql for str {
+    fn to_sql(&self, s: &mut PreparedStatement, ix: ParamIx) -> SqliteResult<()> {
+        s.bind_text(ix, self)
+    }
+}
+
 impl ToSql for String {
     fn to_sql(&self, s: &mut PreparedStatement, ix: ParamIx) -> SqliteResult<()> {
         s.bind_text(ix, (*self).as_ref())
@@ -100,23 +330,179 @@ impl ToSql for String {
 
 
 impl FromSql for String {
+    /// Text that is not valid UTF-8 is an error.
     fn from_sql(row: &ResultRow, col: ColIx) -> SqliteResult<String> {
-        Ok(row.column_text(col).unwrap_or_else(String::new))
+        text_ref(row, col, "String").map(|text| text.to_string())
     }
 }
 
-impl<'a> ToSql for &'a [u8] {
+impl FromSql for Box<str> {
+    fn from_sql(row: &ResultRow, col: ColIx) -> SqliteResult<Box<str>> {
+        String::from_sql(row, col).map(String::into_boxed_str)
+    }
+}
+
+impl FromSql for Rc<str> {
+    fn from_sql(row: &ResultRow, col: ColIx) -> SqliteResult<Rc<str>> {
+        String::from_sql(row, col).map(Rc::from)
+    }
+}
+
+impl ToSql for [u8] {
+    fn to_sql(&self, s: &mut PreparedStatement, ix: ParamIx) -> SqliteResult<()> {
+        s.bind_blob(ix, self)
+    }
+}
+
+impl ToSql for Vec<u8> {
     fn to_sql(&self, s: &mut PreparedStatement, ix: ParamIx) -> SqliteResult<()> {
-        s.bind_blob(ix, *self)
+        s.bind_blob(ix, self)
     }
 }
 
 impl FromSql for Vec<u8> {
     fn from_sql(row: &ResultRow, col: ColIx) -> SqliteResult<Vec<u8>> {
+        try!(check_column_type(row, col, "Vec<u8>", &[SQLITE_BLOB, SQLITE_TEXT]));
         Ok(row.column_blob(col).unwrap_or_else(Vec::new))
     }
 }
 
+impl FromSql for Box<[u8]> {
+    fn from_sql(row: &ResultRow, col: ColIx) -> SqliteResult<Box<[u8]>> {
+        Vec::<u8>::from_sql(row, col).map(Vec::into_boxed_slice)
+    }
+}
+
+impl FromSql for Rc<[u8]> {
+    fn from_sql(row: &ResultRow, col: ColIx) -> SqliteResult<Rc<[u8]>> {
+        Vec::<u8>::from_sql(row, col).map(Rc::from)
+    }
+}
+
+
+/// A column value or parameter of any type.
+///
+/// `FromSql` chooses the variant by `ResultRow::column_type()`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Value {
+    /// `NULL`
+    Null,
+    /// 64-bit signed integer
+    Integer(i64),
+    /// 64-bit IEEE floating point number
+    Real(f64),
+    /// UTF-8 text
+    Text(String),
+    /// Bytes, stored exactly as input
+    Blob(Vec<u8>),
+}
+
+/// A borrowed counterpart of `Value`, e.g. from
+/// `ResultRow::column_value()`.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum ValueRef<'a> {
+    /// `NULL`
+    Null,
+    /// 64-bit signed integer
+    Integer(i64),
+    /// 64-bit IEEE floating point number
+    Real(f64),
+    /// UTF-8 text
+    Text(&'a str),
+    /// Bytes, stored exactly as input
+    Blob(&'a [u8]),
+}
+
+impl Value {
+    /// Storage class of the value.
+    pub fn column_type(&self) -> ColumnType {
+        ValueRef::from(self).column_type()
+    }
+}
+
+impl<'a> ValueRef<'a> {
+    /// Storage class of the value.
+    pub fn column_type(&self) -> ColumnType {
+        match *self {
+            ValueRef::Null => SQLITE_NULL,
+            ValueRef::Integer(_) => SQLITE_INTEGER,
+            ValueRef::Real(_) => SQLITE_FLOAT,
+            ValueRef::Text(_) => SQLITE_TEXT,
+            ValueRef::Blob(_) => SQLITE_BLOB,
+        }
+    }
+}
+
+impl<'a> From<ValueRef<'a>> for Value {
+    fn from(value: ValueRef<'a>) -> Value {
+        match value {
+            ValueRef::Null => Value::Null,
+            ValueRef::Integer(i) => Value::Integer(i),
+            ValueRef::Real(x) => Value::Real(x),
+            ValueRef::Text(s) => Value::Text(s.to_string()),
+            ValueRef::Blob(b) => Value::Blob(b.to_vec()),
+        }
+    }
+}
+
+impl<'a> From<&'a Value> for ValueRef<'a> {
+    fn from(value: &'a Value) -> ValueRef<'a> {
+        match *value {
+            Value::Null => ValueRef::Null,
+            Value::Integer(i) => ValueRef::Integer(i),
+            Value::Real(x) => ValueRef::Real(x),
+            Value::Text(ref s) => ValueRef::Text(s),
+            Value::Blob(ref b) => ValueRef::Blob(b),
+        }
+    }
+}
+
+impl<'a> ToSql for ValueRef<'a> {
+    fn to_sql(&self, s: &mut PreparedStatement, ix: ParamIx) -> SqliteResult<()> {
+        match *self {
+            ValueRef::Null => s.bind_null(ix),
+            ValueRef::Integer(i) => s.bind_int64(ix, i),
+            ValueRef::Real(x) => s.bind_double(ix, x),
+            ValueRef::Text(text) => s.bind_text(ix, text),
+            ValueRef::Blob(bytes) => s.bind_blob(ix, bytes),
+        }
+    }
+}
+
+impl ToSql for Value {
+    fn to_sql(&self, s: &mut PreparedStatement, ix: ParamIx) -> SqliteResult<()> {
+        ValueRef::from(self).to_sql(s, ix)
+    }
+}
+
+impl FromSql for Value {
+    /// Text that is not valid UTF-8 is given as `Value::Blob`.
+    fn from_sql(row: &ResultRow, col: ColIx) -> SqliteResult<Value> {
+        Ok(Value::from(row.column_value(col)))
+    }
+}
+
+impl<'a> FromSqlRef<'a> for ValueRef<'a> {
+    /// Text that is not valid UTF-8 is given as `ValueRef::Blob`.
+    fn from_sql_ref(row: &'a ResultRow, col: ColIx) -> SqliteResult<ValueRef<'a>> {
+        Ok(row.column_value(col))
+    }
+}
+
+
+/// A blob of zeros of the given length, to be filled in later by
+/// incremental I/O.
+///
+/// cf `blob::Blob`
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct ZeroBlob(pub i32);
+
+impl ToSql for ZeroBlob {
+    fn to_sql(&self, s: &mut PreparedStatement, ix: ParamIx) -> SqliteResult<()> {
+        s.bind_zeroblob(ix, self.0)
+    }
+}
+
 
 /// Format of sqlite date strings
 ///
@@ -125,10 +511,317 @@ impl FromSql for Vec<u8> {
 /// [lang_datefunc]: http://www.sqlite.org/lang_datefunc.html
 pub static SQLITE_TIME_FMT: &'static str = "%F %T";
 
+/// How `time::Timespec` and `time::Tm` parameters are stored.
+///
+/// Each is a form that sqlite's [date and time functions][lang_datefunc]
+/// accept; cf `PreparedStatement::set_time_format()`.
+///
+/// Results are read in any of these forms. Numbers are taken as by
+/// sqlite's `auto` modifier: from 0 to 5373484.5 as a Julian day
+/// number, otherwise as seconds since 1970. So under the default
+/// format, an INTEGER `86400` written as Unix seconds by another tool
+/// reads as Julian day 86400, in 4477 B.C. Under `UnixEpoch` and
+/// `UnixEpochReal`, INTEGER and REAL values are always seconds since 1970.
+///
+/// [lang_datefunc]: http://www.sqlite.org/lang_datefunc.html
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub enum TimeFormat {
+    /// TEXT `YYYY-MM-DD HH:MM:SS` in UTC, as from `datetime()`;
+    /// cf `SQLITE_TIME_FMT`. Fractions of a second are dropped.
+    #[default]
+    Iso8601,
+    /// TEXT `YYYY-MM-DD HH:MM:SS.SSSSSSSSS` in UTC.
+    Iso8601Subsec,
+    /// REAL days since noon in Greenwich on November 24, 4714 B.C.,
+    /// as from `julianday()`.
+    JulianDay,
+    /// INTEGER seconds since 1970-01-01 00:00:00 UTC, as from
+    /// `unixepoch()`. Fractions of a second are dropped.
+    UnixEpoch,
+    /// REAL seconds since 1970-01-01 00:00:00 UTC.
+    UnixEpochReal,
+}
+
+const SECS_PER_DAY: i64 = 86400;
+
+/// Julian day number of 1970-01-01 00:00:00 UTC
+const UNIX_EPOCH_JD: f64 = 2440587.5;
+
+/// Days from 1970-01-01 to the given proleptic Gregorian date.
+///
+/// cf Howard Hinnant's [chrono-compatible low-level date algorithms][dates]
+///
+/// [dates]: http://howardhinnant.github.io/date_algorithms.html
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// Inverse of `days_from_civil()`: (year, month, day).
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719468;
+    let era = if z >= 0 { z } else { z - 146096 } / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    (if month <= 2 { yoe + era * 400 + 1 } else { yoe + era * 400 }, month, day)
+}
+
+fn bind_time(s: &mut PreparedStatement, ix: ParamIx, t: time::Timespec) -> SqliteResult<()> {
+    let secs = t.sec as f64 + t.nsec as f64 / 1e9;
+    match s.time_format() {
+        TimeFormat::Iso8601 | TimeFormat::Iso8601Subsec => {
+            let days = t.sec.div_euclid(SECS_PER_DAY);
+            let sod = t.sec.rem_euclid(SECS_PER_DAY);
+            let (year, month, day) = civil_from_days(days);
+            let mut text = format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+                                   year,
+                                   month,
+                                   day,
+                                   sod / 3600,
+                                   sod / 60 % 60,
+                                   sod % 60);
+            if s.time_format() == TimeFormat::Iso8601Subsec {
+                text.push_str(&format!(".{:09}", t.nsec));
+            }
+            s.bind_text(ix, &text)
+        }
+        TimeFormat::JulianDay => s.bind_double(ix, secs / SECS_PER_DAY as f64 + UNIX_EPOCH_JD),
+        TimeFormat::UnixEpoch => s.bind_int64(ix, t.sec),
+        TimeFormat::UnixEpochReal => s.bind_double(ix, secs),
+    }
+}
+
+/// Fields of a time as written in text, with the offset from UTC in
+/// seconds.
+struct TimeText {
+    year: i64,
+    month: i64,
+    day: i64,
+    hour: i64,
+    min: i64,
+    sec: i64,
+    nsec: i64,
+    utcoff: i64,
+}
+
+impl TimeText {
+    fn to_timespec(&self) -> time::Timespec {
+        let days = days_from_civil(self.year, self.month, self.day);
+        let sec = days * SECS_PER_DAY + self.hour * 3600 + self.min * 60 + self.sec - self.utcoff;
+        time::Timespec::new(sec, self.nsec as i32)
+    }
+
+    fn to_tm(&self) -> time::Tm {
+        time::Tm {
+            tm_sec: self.sec as i32,
+            tm_min: self.min as i32,
+            tm_hour: self.hour as i32,
+            tm_mday: self.day as i32,
+            tm_mon: self.month as i32 - 1,
+            tm_year: self.year as i32 - 1900,
+            tm_wday: 0,
+            tm_yday: 0,
+            tm_isdst: 0,
+            tm_utcoff: self.utcoff as i32,
+            tm_nsec: self.nsec as i32,
+        }
+    }
+}
+
+/// Take exactly `n` digits from the front of `text`.
+fn digits(text: &mut &str, n: usize) -> Option<i64> {
+    if text.len() < n || !text.as_bytes()[..n].iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    let value = text[..n].parse().ok();
+    *text = &text[n..];
+    value
+}
+
+/// Take `c` from the front of `text`, if it's there.
+fn punct(text: &mut &str, c: char) -> bool {
+    if text.starts_with(c) {
+        *text = &text[c.len_utf8()..];
+        true
+    } else {
+        false
+    }
+}
+
+/// `HH:MM`, `HH:MM:SS` or `HH:MM:SS.SSS` with any number of fraction
+/// digits: (hour, min, sec, nsec)
+fn parse_time_of_day(text: &mut &str) -> Option<(i64, i64, i64, i64)> {
+    let hour = digits(text, 2)?;
+    if !punct(text, ':') {
+        return None;
+    }
+    let min = digits(text, 2)?;
+    let (mut sec, mut nsec) = (0, 0);
+    if punct(text, ':') {
+        sec = digits(text, 2)?;
+        if punct(text, '.') {
+            let n = text.bytes().take_while(u8::is_ascii_digit).count();
+            if n == 0 {
+                return None;
+            }
+            let frac = format!("{:0<9}", &text[..n.min(9)]);
+            nsec = frac.parse().ok()?;
+            *text = &text[n..];
+        }
+    }
+    if hour > 23 || min > 59 || sec > 59 {
+        return None;
+    }
+    Some((hour, min, sec, nsec))
+}
+
+/// Parse the text forms accepted by sqlite's date and time functions,
+/// other than `now` and numbers: `YYYY-MM-DD`, optionally followed by
+/// a time of day after a space or `T`, or just a time of day (on
+/// 2000-01-01), with an optional time zone `Z` or `[+-]HH:MM`.
+fn parse_time_text(text: &str) -> Option<TimeText> {
+    let mut rest = text.trim();
+    let (mut year, mut month, mut day) = (2000, 1, 1);
+    let mut time_of_day = (0, 0, 0, 0);
+    if rest.len() > 4 && rest.as_bytes()[4] == b'-' {
+        year = digits(&mut rest, 4)?;
+        punct(&mut rest, '-');
+        month = digits(&mut rest, 2)?;
+        if !punct(&mut rest, '-') {
+            return None;
+        }
+        day = digits(&mut rest, 2)?;
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return None;
+        }
+        if punct(&mut rest, ' ') || punct(&mut rest, 'T') {
+            time_of_day = parse_time_of_day(&mut rest)?;
+        }
+    } else {
+        time_of_day = parse_time_of_day(&mut rest)?;
+    }
+    rest = rest.trim_start();
+    let mut utcoff = 0;
+    let zulu = punct(&mut rest, 'Z') || punct(&mut rest, 'z');
+    if !zulu && (rest.starts_with('+') || rest.starts_with('-')) {
+        let sign = if punct(&mut rest, '-') { -1 } else { 1 };
+        punct(&mut rest, '+');
+        let hours = digits(&mut rest, 2)?;
+        if !punct(&mut rest, ':') {
+            return None;
+        }
+        let mins = digits(&mut rest, 2)?;
+        utcoff = sign * (hours * 3600 + mins * 60);
+    }
+    if !rest.is_empty() {
+        return None;
+    }
+    let (hour, min, sec, nsec) = time_of_day;
+    Some(TimeText {
+        year: year,
+        month: month,
+        day: day,
+        hour: hour,
+        min: min,
+        sec: sec,
+        nsec: nsec,
+        utcoff: utcoff,
+    })
+}
+
+/// Interpret a number as sqlite's `auto` modifier does: a Julian day
+/// number from 0.0 to 5373484.499999, otherwise seconds since 1970.
+/// With `unix_epoch`, take it as seconds since 1970 regardless.
+fn time_from_number(value: f64, unix_epoch: bool) -> Option<time::Timespec> {
+    let secs = if !unix_epoch && (0.0..=5373484.499999).contains(&value) {
+        (value - UNIX_EPOCH_JD) * SECS_PER_DAY as f64
+    } else if (-210866760000.0..=253402300799.0).contains(&value) {
+        value
+    } else {
+        return None;
+    };
+    let sec = secs.floor();
+    let nsec = ((secs - sec) * 1e9).round().min(999999999.0);
+    Some(time::Timespec::new(sec as i64, nsec as i32))
+}
+
+/// A time read from a column: either text fields or an instant.
+enum TimeValue {
+    Text(TimeText),
+    Instant(time::Timespec),
+}
+
+fn time_from_sql(row: &ResultRow, col: ColIx, ty: &str) -> SqliteResult<TimeValue> {
+    try!(check_column_type(row, col, ty, &[SQLITE_TEXT, SQLITE_INTEGER, SQLITE_FLOAT]));
+    let unix_epoch = matches!(row.time_format(),
+                              TimeFormat::UnixEpoch | TimeFormat::UnixEpochReal);
+    let found = match row.column_type(col) {
+        SQLITE_INTEGER => {
+            time_from_number(row.column_int64(col) as f64, unix_epoch).map(TimeValue::Instant)
+        }
+        SQLITE_FLOAT => time_from_number(row.column_double(col), unix_epoch).map(TimeValue::Instant),
+        _ => {
+            let text = row.column_str(col).unwrap_or("");
+            match text.trim().parse::<f64>() {
+                Ok(number) => time_from_number(number, false).map(TimeValue::Instant),
+                Err(_) => parse_time_text(text).map(TimeValue::Text),
+            }
+        }
+    };
+    found.ok_or_else(|| {
+        let value = match row.column_value(col) {
+            ValueRef::Text(text) => format!("{:?}", text),
+            other => format!("{:?}", other),
+        };
+        SqliteError {
+            kind: SqliteErrorCode::SQLITE_MISMATCH,
+            desc: "Time did not match expected format",
+            detail: Some(format!("column {}: {}", col, value)),
+        }
+    })
+}
+
 impl FromSql for time::Tm {
+    /// Accept any form of time that sqlite's date and time functions
+    /// accept, other than `now`; numbers are interpreted as by the
+    /// `auto` modifier, except that INTEGER and REAL values are
+    /// seconds since 1970 when the statement's `TimeFormat` is
+    /// `UnixEpoch` or `UnixEpochReal`.
+    ///
+    /// Text is taken field by field (`tm_wday` and `tm_yday` are 0),
+    /// with `tm_utcoff` from any time zone; numbers give a time in UTC.
     fn from_sql(row: &ResultRow, col: ColIx) -> SqliteResult<time::Tm> {
-        let txt = row.column_text(col).unwrap_or_else(String::new);
-        Ok(try!(time::strptime(txt.as_ref(), SQLITE_TIME_FMT)))
+        match try!(time_from_sql(row, col, "Tm")) {
+            TimeValue::Text(fields) => Ok(fields.to_tm()),
+            TimeValue::Instant(t) => Ok(time::at_utc(t)),
+        }
+    }
+}
+
+impl ToSql for time::Tm {
+    /// Bind the instant `tm` denotes, taking `tm_utcoff` into account,
+    /// in the statement's `TimeFormat`. Text is written in UTC.
+    fn to_sql(&self, s: &mut PreparedStatement, ix: ParamIx) -> SqliteResult<()> {
+        let fields = TimeText {
+            year: self.tm_year as i64 + 1900,
+            month: self.tm_mon as i64 + 1,
+            day: self.tm_mday as i64,
+            hour: self.tm_hour as i64,
+            min: self.tm_min as i64,
+            sec: self.tm_sec as i64,
+            nsec: self.tm_nsec as i64,
+            utcoff: self.tm_utcoff as i64,
+        };
+        bind_time(s, ix, fields.to_timespec())
     }
 }
 
@@ -143,19 +836,136 @@ impl From<time::ParseError> for SqliteError {
 }
 
 impl ToSql for time::Timespec {
+    /// Bind in the statement's `TimeFormat`.
     fn to_sql(&self, s: &mut PreparedStatement, ix: ParamIx) -> SqliteResult<()> {
-        let timestr = time::at_utc(*self).strftime(SQLITE_TIME_FMT)
-            .unwrap() // unit tests ensure SQLITE_TIME_FMT is ok
-            .to_string();
-        s.bind_text(ix, timestr.as_ref())
+        bind_time(s, ix, *self)
     }
 }
 
 impl FromSql for time::Timespec {
-    /// TODO: propagate error message
+    /// Accept the same forms as `FromSql for time::Tm`.
     fn from_sql(row: &ResultRow, col: ColIx) -> SqliteResult<time::Timespec> {
-        let tmo: SqliteResult<time::Tm> = FromSql::from_sql(row, col);
-        tmo.map(|tm| tm.to_timespec())
+        match try!(time_from_sql(row, col, "Timespec")) {
+            TimeValue::Text(fields) => Ok(fields.to_timespec()),
+            TimeValue::Instant(t) => Ok(t),
+        }
+    }
+}
+
+/// Unit in which `time::Duration` and `std::time::Duration` values
+/// are stored; cf `PreparedStatement::set_duration_unit()`.
+///
+/// Results may be INTEGER or REAL in any unit.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub enum DurationUnit {
+    /// INTEGER milliseconds; finer precision is dropped.
+    #[default]
+    Milliseconds,
+    /// INTEGER microseconds; finer precision is dropped.
+    Microseconds,
+    /// REAL seconds
+    Seconds,
+}
+
+impl DurationUnit {
+    fn in_seconds(&self) -> f64 {
+        match *self {
+            DurationUnit::Milliseconds => 1e-3,
+            DurationUnit::Microseconds => 1e-6,
+            DurationUnit::Seconds => 1.0,
+        }
+    }
+}
+
+fn duration_error(desc: &'static str, value: String, col: Option<ColIx>) -> SqliteError {
+    SqliteError {
+        kind: SqliteErrorCode::SQLITE_MISMATCH,
+        desc: desc,
+        detail: Some(match col {
+            Some(col) => format!("column {}: {}", col, value),
+            None => value,
+        }),
+    }
+}
+
+/// Read a duration as seconds and nanoseconds, with the same sign.
+fn duration_from_sql(row: &ResultRow, col: ColIx, ty: &str) -> SqliteResult<(i64, i64)> {
+    try!(check_column_type(row, col, ty, &[SQLITE_INTEGER, SQLITE_FLOAT]));
+    let unit = row.duration_unit();
+    if row.column_type(col) == SQLITE_INTEGER {
+        let n = row.column_int64(col);
+        return Ok(match unit {
+            DurationUnit::Milliseconds => (n / 1000, n % 1000 * 1000000),
+            DurationUnit::Microseconds => (n / 1000000, n % 1000000 * 1000),
+            DurationUnit::Seconds => (n, 0),
+        });
+    }
+    let secs = row.column_double(col) * unit.in_seconds();
+    if !secs.is_finite() || secs.abs() >= i64::max_value() as f64 {
+        return Err(duration_error("duration out of range", format!("{}", secs), Some(col)));
+    }
+    let whole = secs.trunc();
+    Ok((whole as i64, ((secs - whole) * 1e9).round() as i64))
+}
+
+impl ToSql for time::Duration {
+    /// Bind in the statement's `DurationUnit`.
+    fn to_sql(&self, s: &mut PreparedStatement, ix: ParamIx) -> SqliteResult<()> {
+        match s.duration_unit() {
+            DurationUnit::Milliseconds => s.bind_int64(ix, self.num_milliseconds()),
+            DurationUnit::Microseconds => {
+                match self.num_microseconds() {
+                    Some(us) => s.bind_int64(ix, us),
+                    None => {
+                        Err(duration_error("duration out of range", format!("{}", self), None))
+                    }
+                }
+            }
+            DurationUnit::Seconds => {
+                let secs = self.num_seconds();
+                let nanos = (*self - time::Duration::seconds(secs)).num_nanoseconds().unwrap_or(0);
+                s.bind_double(ix, secs as f64 + nanos as f64 / 1e9)
+            }
+        }
+    }
+}
+
+impl FromSql for time::Duration {
+    fn from_sql(row: &ResultRow, col: ColIx) -> SqliteResult<time::Duration> {
+        let (secs, nanos) = try!(duration_from_sql(row, col, "Duration"));
+        // time::Duration::seconds() panics beyond i64::MAX milliseconds
+        let limit = i64::max_value() / 1000 - 1;
+        if secs < -limit || secs > limit {
+            return Err(duration_error("duration out of range", format!("{}s", secs), Some(col)));
+        }
+        Ok(time::Duration::seconds(secs) + time::Duration::nanoseconds(nanos))
+    }
+}
+
+impl ToSql for StdDuration {
+    /// Bind in the statement's `DurationUnit`.
+    fn to_sql(&self, s: &mut PreparedStatement, ix: ParamIx) -> SqliteResult<()> {
+        let count = match s.duration_unit() {
+            DurationUnit::Milliseconds => self.as_millis(),
+            DurationUnit::Microseconds => self.as_micros(),
+            DurationUnit::Seconds => return s.bind_double(ix, self.as_secs_f64()),
+        };
+        match i64::try_from(count) {
+            Ok(count) => s.bind_int64(ix, count),
+            Err(_) => Err(duration_error("duration out of range", format!("{:?}", self), None)),
+        }
+    }
+}
+
+impl FromSql for StdDuration {
+    /// Fail with `SQLITE_MISMATCH` on negative values.
+    fn from_sql(row: &ResultRow, col: ColIx) -> SqliteResult<StdDuration> {
+        let (secs, nanos) = try!(duration_from_sql(row, col, "std::time::Duration"));
+        if secs < 0 || nanos < 0 {
+            let value = format!("{}s", secs as f64 + nanos as f64 / 1e9);
+            return Err(duration_error("negative duration", value, Some(col)));
+        }
+        Ok(StdDuration::new(secs as u64, nanos as u32))
     }
 }
 
@@ -206,6 +1016,157 @@ mod tests {
         go().unwrap();
     }
 
+    #[test]
+    fn time_formats() {
+        use time::Timespec;
+        use super::TimeFormat;
+        use super::super::{QueryRow, ResultRow};
+
+        let t = Timespec::new(1000000000, 123456789);
+        let mut db = DatabaseConnection::in_memory().unwrap();
+        let formats = [(TimeFormat::Iso8601, "text", "+0 seconds"),
+                       (TimeFormat::Iso8601Subsec, "text", "+0 seconds"),
+                       (TimeFormat::JulianDay, "real", "+0 seconds"),
+                       (TimeFormat::UnixEpoch, "integer", "unixepoch"),
+                       (TimeFormat::UnixEpochReal, "real", "unixepoch")];
+        for &(format, storage, modifier) in formats.iter() {
+            db.set_time_format(format);
+            let mut stmt = db.prepare("select $1, typeof($1), datetime($1, $2)").unwrap();
+            let get = |row: &mut ResultRow| {
+                Ok((row.get::<u32, Timespec>(0), row.get::<u32, String>(1),
+                    row.get::<u32, String>(2)))
+            };
+            let (back, typ, datetime) = stmt.query_row(&[&t, &modifier], get).unwrap();
+            assert_eq!((typ.as_ref(), datetime.as_ref()), (storage, "2001-09-09 01:46:40"));
+            assert_eq!(back.sec, t.sec);
+            let nsec = match format {
+                TimeFormat::Iso8601 | TimeFormat::UnixEpoch => 0,
+                _ => t.nsec,
+            };
+            assert!((back.nsec - nsec).abs() < 100000, "{:?}: {:?}", format, back);
+        }
+    }
+
+    #[test]
+    fn time_formats_near_epoch() {
+        use time::Timespec;
+        use super::TimeFormat;
+        use super::super::{QueryRow, ResultRow};
+
+        let mut db = DatabaseConnection::in_memory().unwrap();
+        let formats = [TimeFormat::Iso8601,
+                       TimeFormat::Iso8601Subsec,
+                       TimeFormat::JulianDay,
+                       TimeFormat::UnixEpoch,
+                       TimeFormat::UnixEpochReal];
+        for &format in formats.iter() {
+            db.set_time_format(format);
+            let mut stmt = db.prepare("select $1").unwrap();
+            for &sec in [-86400, 0, 1, 86400, 62 * 86400].iter() {
+                let t = Timespec::new(sec, 0);
+                let back = stmt.query_row(&[&t], |row: &mut ResultRow| {
+                        Ok(row.get::<u32, Timespec>(0))
+                    })
+                    .unwrap();
+                let error = (back - t).num_microseconds().unwrap();
+                assert!(error.abs() < 100, "{:?}: {:?} for {}", format, back, sec);
+            }
+        }
+    }
+
+    #[test]
+    fn detect_time_forms() {
+        use time::{Timespec, Tm, empty_tm};
+        use super::super::{QueryRow, ResultRow, ToSql};
+
+        let db = DatabaseConnection::in_memory().unwrap();
+        let mut stmt = db.prepare("select $1").unwrap();
+        let mut read = |value: &ToSql| {
+            stmt.query_row(&[value], |row: &mut ResultRow| Ok(row.get::<u32, Timespec>(0)))
+                .unwrap()
+                .sec
+        };
+        let day = 978480000; // 2001-01-03 00:00:00 UTC
+        assert_eq!(read(&"2001-01-03"), day);
+        assert_eq!(read(&"2001-01-03T03:04"), day + 3 * 3600 + 4 * 60);
+        assert_eq!(read(&"2001-01-03 03:00:00.5+05:00"), day - 2 * 3600);
+        assert_eq!(read(&"2001-01-03 03:00:00Z"), day + 3 * 3600);
+        assert_eq!(read(&"03:04:05"), 946684800 + 3 * 3600 + 4 * 60 + 5);
+        assert_eq!(read(&"2451912.5"), day);
+        assert_eq!(read(&2451912.5), day);
+        assert_eq!(read(&day), day);
+        // a Julian day number, under the default format
+        assert_eq!(read(&86400), (86400 * 86400) - (2440587 * 86400 + 43200));
+
+        let tm = Tm {
+            tm_hour: 8,
+            tm_mday: 3,
+            tm_year: 101,
+            tm_utcoff: 5 * 3600,
+            ..empty_tm()
+        };
+        let stored = stmt.query_row(&[&tm], |row| Ok(row.get::<u32, String>(0))).unwrap();
+        assert_eq!(stored, "2001-01-03 03:00:00");
+        let back = stmt.query_row(&[&"2001-01-03 08:00:00+05:00"],
+                                  |row| Ok(row.get::<u32, Tm>(0)))
+            .unwrap();
+        assert_eq!(back, tm);
+    }
+
+    #[test]
+    fn invalid_time_detail() {
+        with_query("select 1, 'nope'", |results| {
+                let row = results.step().unwrap().unwrap();
+                let err = row.get_opt::<u32, Tm>(1).err().unwrap();
+                assert_eq!(err.detail(), Some("column 1: \"nope\"".to_string()));
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn durations() {
+        use std::time::Duration as StdDuration;
+        use time::Duration;
+        use super::DurationUnit;
+        use super::super::{QueryRow, ResultRow};
+
+        let mut db = DatabaseConnection::in_memory().unwrap();
+        let d = Duration::milliseconds(1500) + Duration::microseconds(250);
+        let std_d = StdDuration::new(1, 500250000);
+        let stored = |db: &DatabaseConnection| {
+            let mut stmt = db.prepare("select $1, $2, $1 = $2, typeof($1)").unwrap();
+            let get = |row: &mut ResultRow| {
+                Ok((row.get::<u32, Duration>(0), row.get::<u32, StdDuration>(1),
+                    row.get::<u32, bool>(2), row.get::<u32, String>(3)))
+            };
+            stmt.query_row(&[&d, &std_d], get).unwrap()
+        };
+        let ms = (Duration::milliseconds(1500), StdDuration::from_millis(1500));
+        assert_eq!(stored(&db), (ms.0, ms.1, true, "integer".to_string()));
+        db.set_duration_unit(DurationUnit::Microseconds);
+        assert_eq!(stored(&db), (d, std_d, true, "integer".to_string()));
+        db.set_duration_unit(DurationUnit::Seconds);
+        assert_eq!(stored(&db), (d, std_d, true, "real".to_string()));
+
+        with_query("select -5, 2.5", |results| {
+                let row = results.step().unwrap().unwrap();
+                assert_eq!(row.get::<u32, Duration>(0), Duration::milliseconds(-5));
+                assert_eq!(row.get::<u32, Duration>(1), Duration::microseconds(2500));
+                let err = row.get_opt::<u32, StdDuration>(0).err().unwrap();
+                assert_eq!(err.desc, "negative duration");
+                assert_eq!(err.detail(), Some("column 0: -0.005s".to_string()));
+            })
+            .unwrap();
+
+        let mut stmt = db.prepare("select -9223372036854775808, 9223372036854775807").unwrap();
+        let mut results = stmt.execute();
+        let row = results.step().unwrap().unwrap();
+        for col in 0..2 {
+            let err = row.get_opt::<u32, Duration>(col).err().unwrap();
+            assert_eq!(err.desc, "duration out of range");
+        }
+    }
+
     #[test]
     fn get_invalid_tm() {
         with_query("select 'not a time'", |results| {
@@ -221,6 +1182,185 @@ mod tests {
             .unwrap();
     }
 
+    #[test]
+    fn integer_range() {
+        use super::super::SqliteErrorCode::SQLITE_MISMATCH;
+
+        with_query("select 255, 256, -1, 9223372036854775807, 1e300, 4294967296", |results| {
+                let row = results.step().unwrap().unwrap();
+                assert_eq!(row.get_opt::<u32, u8>(0), Ok(255));
+                assert_eq!(row.get_opt::<u32, i16>(1), Ok(256));
+                let err = row.get_opt::<u32, u8>(1).err().unwrap();
+                assert_eq!(err.kind, SQLITE_MISMATCH);
+                assert_eq!(err.detail(), Some("column 1: 256 does not fit in u8".to_string()));
+                assert!(row.get_opt::<u32, u64>(2).is_err());
+                assert_eq!(row.get_opt::<u32, i8>(2), Ok(-1));
+                assert!(row.get_opt::<u32, i32>(3).is_err());
+                assert_eq!(row.get_opt::<u32, u64>(3), Ok(9223372036854775807));
+                assert!(row.get_opt::<u32, f32>(4).is_err());
+                assert_eq!(row.get_opt::<u32, f64>(4), Ok(1e300));
+                assert_eq!(row.get_opt::<u32, bool>(5), Ok(true));
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn bind_unsigned() {
+        use super::super::{QueryRow, ResultRow};
+
+        let db = DatabaseConnection::in_memory().unwrap();
+        let mut stmt = db.prepare("select $1").unwrap();
+        let get = |row: &mut ResultRow| Ok(row.get::<u32, u64>(0));
+        assert_eq!(stmt.query_row(&[&(i64::max_value() as u64)], get),
+                   Ok(i64::max_value() as u64));
+        let err = stmt.query_row(&[&u64::max_value()], get).err().unwrap();
+        assert_eq!(err.detail(),
+                   Some("18446744073709551615 does not fit in an sqlite INTEGER (i64)"
+                       .to_string()));
+        assert_eq!(stmt.query_row(&[&7u8], |row| Ok(row.get::<u32, usize>(0))), Ok(7));
+        assert_eq!(stmt.query_row(&[&1.5f32], |row| Ok(row.get::<u32, f32>(0))), Ok(1.5));
+    }
+
+    #[test]
+    fn strict_types() {
+        use super::super::SqliteErrorCode::SQLITE_MISMATCH;
+
+        let mut db = DatabaseConnection::in_memory().unwrap();
+        let sql = "select null as n, 'abc' as t, 2 as i, x'ff' as b";
+        {
+            let mut stmt = db.prepare(sql).unwrap();
+            let mut results = stmt.execute();
+            let row = results.step().unwrap().unwrap();
+            assert!(!row.is_strict());
+            assert_eq!(row.get_opt::<u32, String>(0), Ok("".to_string()));
+            assert_eq!(row.get_opt::<u32, i32>(1), Ok(0));
+        }
+
+        db.set_strict(true);
+        let mut stmt = db.prepare(sql).unwrap();
+        {
+            let mut results = stmt.execute();
+            let row = results.step().unwrap().unwrap();
+            let err = row.get_opt::<u32, String>(0).err().unwrap();
+            assert_eq!(err.kind, SQLITE_MISMATCH);
+            assert_eq!(err.detail(),
+                       Some("column 0 (n): can't get String from SQLITE_NULL".to_string()));
+            assert_eq!(row.get_opt::<u32, Option<String>>(0), Ok(None));
+            assert_eq!(row.get_opt::<u32, Option<Vec<u8>>>(0), Ok(None));
+            assert!(row.get_opt::<u32, i32>(1).is_err());
+            assert_eq!(row.get_opt::<u32, String>(1), Ok("abc".to_string()));
+            assert_eq!(row.get_opt::<u32, f64>(2), Ok(2.0));
+            assert!(row.get_opt::<u32, String>(2).is_err());
+            assert!(row.get_opt::<u32, String>(3).is_err());
+            assert_eq!(row.get_opt::<u32, Vec<u8>>(3), Ok(vec![0xff]));
+        }
+        {
+            let mut stmt = db.prepare("select 9007199254740992, 9007199254740993,
+                                              16777216, 16777217, 9223372036854775807")
+                .unwrap();
+            let mut results = stmt.execute();
+            let row = results.step().unwrap().unwrap();
+            assert_eq!(row.get_opt::<u32, f64>(0), Ok(9007199254740992.0));
+            let err = row.get_opt::<u32, f64>(1).err().unwrap();
+            assert_eq!(err.kind, SQLITE_MISMATCH);
+            assert_eq!(err.detail(),
+                       Some("column 1: 9007199254740993 is not exact in f64".to_string()));
+            assert_eq!(row.get_opt::<u32, f32>(2), Ok(16777216.0));
+            assert!(row.get_opt::<u32, f32>(3).is_err());
+            assert_eq!(row.get_opt::<u32, f64>(3), Ok(16777217.0));
+            assert!(row.get_opt::<u32, f64>(4).is_err());
+        }
+
+        stmt.set_strict(false);
+        let mut results = stmt.execute();
+        let row = results.step().unwrap().unwrap();
+        assert_eq!(row.get_opt::<u32, Vec<u8>>(0), Ok(vec![]));
+    }
+
+    #[test]
+    fn dynamic_values() {
+        use super::super::{QueryRow, ResultRow};
+        use super::{Value, ValueRef};
+        use super::super::ColumnType::SQLITE_FLOAT;
+
+        let db = DatabaseConnection::in_memory().unwrap();
+        let mut stmt = db.prepare("select null, 1, 1.5, 'a', x'00', cast(x'ff' as text)").unwrap();
+        let values = stmt.query_row(&[], |row| Ok(row.values())).unwrap();
+        assert_eq!(values,
+                   vec![Value::Null,
+                        Value::Integer(1),
+                        Value::Real(1.5),
+                        Value::Text("a".to_string()),
+                        Value::Blob(vec![0]),
+                        Value::Blob(vec![0xff])]);
+        assert_eq!(values[2].column_type(), SQLITE_FLOAT);
+
+        let mut echo = db.prepare("select $1, $2").unwrap();
+        for value in values.iter() {
+            let same = |row: &mut ResultRow| {
+                assert_eq!(row.column_value(0), ValueRef::from(value));
+                Ok(row.get::<u32, Value>(1))
+            };
+            assert_eq!(echo.query_row(&[value, &ValueRef::from(value)], same),
+                       Ok(value.clone()));
+        }
+    }
+
+    #[test]
+    fn borrowed_params() {
+        use std::borrow::Cow;
+        use std::rc::Rc;
+        use super::super::{QueryRow, ResultRow, ToSql};
+
+        struct NoClone(i32);
+        impl ToSql for NoClone {
+            fn to_sql(&self, s: &mut super::PreparedStatement, ix: u16) -> SqliteResult<()> {
+                self.0.to_sql(s, ix)
+            }
+        }
+
+        let db = DatabaseConnection::in_memory().unwrap();
+        let mut stmt = db.prepare("select $1 || $2 || $3 || $4, $5 || $6, $7").unwrap();
+        let text = "b".to_string();
+        let params: [&ToSql; 7] = [&"a",
+                                   &&text,
+                                   &Box::<str>::from("c"),
+                                   &Rc::new("d".to_string()),
+                                   &Cow::Borrowed("e"),
+                                   &vec![b'f'],
+                                   &Some(NoClone(7))];
+        let get = |row: &mut ResultRow| {
+            Ok((row.get::<u32, Rc<str>>(0), row.get::<u32, Box<[u8]>>(1), row.get::<u32, i32>(2)))
+        };
+        let (abcd, ef, seven) = stmt.query_row(&params, get).unwrap();
+        assert_eq!(&*abcd, "abcd");
+        assert_eq!(&*ef, b"ef");
+        assert_eq!(seven, 7);
+    }
+
+    #[test]
+    fn borrowed_results() {
+        use super::super::SqliteErrorCode::SQLITE_MISMATCH;
+        use super::ValueRef;
+
+        with_query("select 'abc', x'0102', null, cast(x'ff' as text)", |results| {
+                let row = results.step().unwrap().unwrap();
+                let abc: &str = row.get(0u32);
+                let bytes: &[u8] = row.get(1u32);
+                assert_eq!((abc, bytes), ("abc", &[1u8, 2][..]));
+                assert_eq!(row.get::<u32, Option<&str>>(2), None);
+                assert_eq!(row.get::<u32, Option<&[u8]>>(1), Some(&[1u8, 2][..]));
+                assert_eq!(row.get::<u32, &str>(2), "");
+                assert_eq!(row.get::<u32, ValueRef>(0), ValueRef::Text("abc"));
+
+                let err = row.get_opt::<u32, &str>(3).err().unwrap();
+                assert_eq!(err.kind, SQLITE_MISMATCH);
+                assert!(row.get_opt::<u32, String>(3).is_err());
+                assert_eq!(row.get::<u32, &[u8]>(3), &[0xff][..]);
+            })
+            .unwrap();
+    }
+
     #[test]
     fn select_blob() {
         with_query("select x'ff0db0'", |results| {
@@ -0,0 +1,82 @@
+#![cfg(feature = "derive")]
+
+extern crate sqlite3;
+
+use sqlite3::{DatabaseConnection, FromRow, Query, SqliteErrorCode};
+
+#[derive(Debug, PartialEq, FromRow)]
+struct Person {
+    id: i32,
+    #[sqlite3(rename = "full_name")]
+    name: String,
+    #[sqlite3(default)]
+    visits: i64,
+    email: Option<String>,
+    nickname: Option<Option<String>>,
+}
+
+#[test]
+fn derive_by_name() {
+    let mut conn = DatabaseConnection::in_memory().unwrap();
+    conn.exec("CREATE TABLE person (id INTEGER PRIMARY KEY, full_name TEXT, visits INTEGER,
+                                    email TEXT, nickname TEXT);
+               INSERT INTO person VALUES (1, 'Dan', 3, 'dan@example.com', 'dc');
+               INSERT INTO person VALUES (2, 'Pete', NULL, NULL, NULL);")
+        .unwrap();
+    let mut stmt = conn.prepare("SELECT * FROM person ORDER BY id").unwrap();
+    let people: Vec<Person> = stmt.query(&[], Person::from_row)
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(people,
+               vec![Person {
+                        id: 1,
+                        name: "Dan".to_string(),
+                        visits: 3,
+                        email: Some("dan@example.com".to_string()),
+                        nickname: Some(Some("dc".to_string())),
+                    },
+                    Person {
+                        id: 2,
+                        name: "Pete".to_string(),
+                        visits: 0,
+                        email: None,
+                        nickname: Some(None),
+                    }]);
+}
+
+#[test]
+fn derive_missing_columns() {
+    let mut conn = DatabaseConnection::in_memory().unwrap();
+    conn.exec("CREATE TABLE person (id INTEGER PRIMARY KEY, full_name TEXT);
+               INSERT INTO person VALUES (1, 'Dan');")
+        .unwrap();
+    let mut stmt = conn.prepare("SELECT full_name, id FROM person WHERE id = 1").unwrap();
+    let person = stmt.query(&[], Person::from_row).unwrap().next().unwrap().unwrap();
+    assert_eq!(person.visits, 0);
+    assert_eq!(person.email, None);
+    assert_eq!(person.nickname, None);
+
+    let mut stmt = conn.prepare("SELECT id FROM person").unwrap();
+    let err = stmt.query(&[], Person::from_row).unwrap().next().unwrap().err().unwrap();
+    assert_eq!(err.kind, SqliteErrorCode::SQLITE_MISUSE);
+    assert_eq!(err.detail(), Some("full_name".to_string()));
+}
+
+#[test]
+fn from_row_by_hand() {
+    struct Count(i64);
+    impl FromRow for Count {
+        fn from_row(row: &mut sqlite3::ResultRow) -> sqlite3::SqliteResult<Count> {
+            use sqlite3::ResultRowAccess;
+            row.get_opt(0u32).map(Count)
+        }
+    }
+    let conn = DatabaseConnection::in_memory().unwrap();
+    let mut stmt = conn.prepare("SELECT 2").unwrap();
+    let counts = stmt.query(&[], Count::from_row)
+        .unwrap()
+        .map(|c| c.unwrap().0)
+        .collect::<Vec<_>>();
+    assert_eq!(counts, vec![2]);
+}
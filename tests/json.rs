@@ -0,0 +1,88 @@
+#![cfg(feature = "json")]
+
+extern crate sqlite3;
+#[macro_use]
+extern crate serde_derive;
+
+use sqlite3::{DatabaseConnection, FromSql, ResultRowAccess, SqliteErrorCode, StatementUpdate};
+use sqlite3::json::{self, Json, JsonPath};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Doc {
+    title: String,
+    tags: Vec<String>,
+}
+
+#[test]
+fn round_trip() {
+    let mut conn = DatabaseConnection::in_memory().unwrap();
+    conn.exec("CREATE TABLE doc (body TEXT)").unwrap();
+    {
+        let mut ins = conn.prepare("INSERT INTO doc VALUES ($1)").unwrap();
+        let doc = Doc {
+            title: "it's here".to_string(),
+            tags: vec!["a".to_string(), "b".to_string()],
+        };
+        ins.update(&[&Json(doc)]).unwrap();
+    }
+    let mut stmt = conn.prepare("SELECT body FROM doc").unwrap();
+    let mut rows = stmt.execute();
+    let row = rows.step().unwrap().unwrap();
+    let Json(doc): Json<Doc> = row.get(0u32);
+    assert_eq!(doc.tags, vec!["a".to_string(), "b".to_string()]);
+    let text: String = row.get(0u32);
+    assert_eq!(text, r#"{"title":"it's here","tags":["a","b"]}"#);
+}
+
+#[test]
+fn invalid_json() {
+    let conn = DatabaseConnection::in_memory().unwrap();
+    let mut stmt = conn.prepare("SELECT '{not json'").unwrap();
+    let mut rows = stmt.execute();
+    let row = rows.step().unwrap().unwrap();
+    let err = <Json<Doc> as FromSql>::from_sql(&row, 0).err().unwrap();
+    assert_eq!(err.kind, SqliteErrorCode::SQLITE_MISMATCH);
+    assert_eq!(err.desc, "invalid JSON");
+}
+
+#[test]
+fn paths() {
+    assert!(JsonPath::parse("$").is_ok());
+    assert!(JsonPath::parse("$.a.b[0]").is_ok());
+    assert!(JsonPath::parse("$.\"x.y\"[#-1]").is_ok());
+    assert!(JsonPath::parse("$[#]").is_ok());
+    assert_eq!(JsonPath::root().to_string(), "$");
+    for bad in &["", "a", "$.", "$[", "$[x]", "$[-1]", "$.\"open", "$a"] {
+        let err = JsonPath::parse(bad).err().unwrap();
+        assert_eq!(err.kind, SqliteErrorCode::SQLITE_MISUSE);
+    }
+    let path = JsonPath::parse("$.it's").unwrap();
+    assert_eq!(json::extract_expr("my \"col\"", &path),
+               r#"json_extract("my ""col""", '$.it''s')"#);
+}
+
+#[test]
+fn extract_and_each() {
+    if json::check_json1().is_err() {
+        assert!(!json::json1_available());
+        return;
+    }
+    let mut conn = DatabaseConnection::in_memory().unwrap();
+    conn.exec(r#"CREATE TABLE doc (body TEXT);
+                 INSERT INTO doc VALUES ('{"title":"x","tags":["a","b"]}');"#)
+        .unwrap();
+    let path = JsonPath::parse("$.tags[1]").unwrap();
+    let mut stmt = conn.prepare(&json::extract_sql("doc", "body", &path)).unwrap();
+    let mut rows = stmt.execute();
+    let row = rows.step().unwrap().unwrap();
+    assert_eq!(row.get::<_, String>("value"), "b");
+
+    let path = JsonPath::parse("$.tags").unwrap();
+    let mut stmt = conn.prepare(&json::each_sql("doc", "body", &path)).unwrap();
+    let mut rows = stmt.execute();
+    let mut tags = vec![];
+    while let Some(row) = rows.step().unwrap() {
+        tags.push((row.get::<_, i64>("key"), row.get::<_, String>("value")));
+    }
+    assert_eq!(tags, vec![(0, "a".to_string()), (1, "b".to_string())]);
+}
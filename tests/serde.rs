@@ -0,0 +1,122 @@
+#![cfg(feature = "serde")]
+
+extern crate sqlite3;
+#[macro_use]
+extern crate serde_derive;
+
+use std::collections::BTreeMap;
+
+use sqlite3::{DatabaseConnection, SqliteErrorCode, StatementUpdate};
+use sqlite3::serde_sql::{bind_named, bind_named_lenient, from_row};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum Status {
+    Active,
+    Retired,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Person {
+    id: i64,
+    name: String,
+    active: bool,
+    email: Option<String>,
+    status: Status,
+    photo: Vec<u8>,
+}
+
+#[test]
+fn round_trip() {
+    let mut conn = DatabaseConnection::in_memory().unwrap();
+    conn.exec("CREATE TABLE person (id INTEGER PRIMARY KEY, name TEXT, active INTEGER,
+                                    email TEXT, status TEXT, photo BLOB)")
+        .unwrap();
+    let dan = Person {
+        id: 1,
+        name: "Dan".to_string(),
+        active: true,
+        email: None,
+        status: Status::Retired,
+        photo: vec![1, 2, 3],
+    };
+    {
+        let mut ins = conn.prepare("INSERT INTO person
+                                    VALUES (:id, :name, :active, :email, :status, :photo)")
+            .unwrap();
+        bind_named(&mut ins, &dan).unwrap();
+        assert_eq!(ins.update(&[]).unwrap(), 1);
+    }
+
+    let mut stmt = conn.prepare("SELECT * FROM person").unwrap();
+    let mut rows = stmt.execute();
+    let row = rows.step().unwrap().unwrap();
+    let got: Person = from_row(&row).unwrap();
+    assert_eq!(got, dan);
+}
+
+#[test]
+fn borrowed_fields_and_tuples() {
+    #[derive(Deserialize)]
+    struct Name<'a> {
+        name: &'a str,
+    }
+
+    let conn = DatabaseConnection::in_memory().unwrap();
+    let mut stmt = conn.prepare("SELECT 'Dan' AS name, 7 AS n").unwrap();
+    let mut rows = stmt.execute();
+    let row = rows.step().unwrap().unwrap();
+    let name: Name = from_row(&row).unwrap();
+    assert_eq!(name.name, "Dan");
+    let pair: (String, u8) = from_row(&row).unwrap();
+    assert_eq!(pair, ("Dan".to_string(), 7));
+
+    let mut stmt = conn.prepare("SELECT 1 AS a, 2 AS b").unwrap();
+    let mut rows = stmt.execute();
+    let row = rows.step().unwrap().unwrap();
+    let map: BTreeMap<String, i64> = from_row(&row).unwrap();
+    assert_eq!(map.get("b"), Some(&2));
+}
+
+#[test]
+fn map_params_and_errors() {
+    let mut conn = DatabaseConnection::in_memory().unwrap();
+    conn.exec("CREATE TABLE person (id INTEGER PRIMARY KEY, name TEXT)").unwrap();
+    let mut ins = conn.prepare("INSERT INTO person (id, name) VALUES ($id, @name)").unwrap();
+    let mut params = BTreeMap::new();
+    params.insert("id", "9");
+    params.insert("name", "Pete");
+    params.insert("unused", "x");
+    let err = bind_named(&mut ins, &params).err().unwrap();
+    assert_eq!(err.kind, SqliteErrorCode::SQLITE_RANGE);
+    assert_eq!(err.detail(), Some("unused".to_string()));
+    bind_named_lenient(&mut ins, &params).unwrap();
+    assert_eq!(ins.update(&[]).unwrap(), 1);
+
+    // parameters left out are NULL, not whatever was bound before
+    let mut id_only = BTreeMap::new();
+    id_only.insert("id", "10");
+    bind_named(&mut ins, &id_only).unwrap();
+    assert_eq!(ins.update(&[]).unwrap(), 1);
+    let mut stmt = conn.prepare("SELECT name IS NULL FROM person WHERE id = 10").unwrap();
+    let mut rows = stmt.execute();
+    assert_eq!(rows.step().unwrap().unwrap().column_int(0), 1);
+
+    let err = bind_named(&mut ins, &42).err().unwrap();
+    assert_eq!(err.kind, SqliteErrorCode::SQLITE_MISMATCH);
+
+    let mut big = BTreeMap::new();
+    big.insert("id", u64::max_value());
+    let err = bind_named(&mut ins, &big).err().unwrap();
+    assert_eq!(err.kind, SqliteErrorCode::SQLITE_MISMATCH);
+
+    let mut stmt = conn.prepare("SELECT name AS id FROM person").unwrap();
+    let mut rows = stmt.execute();
+    let row = rows.step().unwrap().unwrap();
+    #[derive(Debug, Deserialize)]
+    struct Id {
+        #[allow(dead_code)]
+        id: i64,
+    }
+    let err = from_row::<Id>(&row).err().unwrap();
+    assert_eq!(err.kind, SqliteErrorCode::SQLITE_MISMATCH);
+}